@@ -0,0 +1,19 @@
+use std::path::PathBuf;
+
+/// folder of the assets, the same one `AssetServer` loads from:
+/// the crate directory under cargo, otherwise next to the executable
+fn asset_root() -> PathBuf {
+  match std::env::var_os("CARGO_MANIFEST_DIR") {
+    Some(dir) => PathBuf::from(dir),
+    None => std::env::current_exe()
+      .ok()
+      .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+      .unwrap_or_else(|| PathBuf::from(".")),
+  }
+  .join("assets")
+}
+
+/// path on disk of an asset, `path` is relative to `assets` like `AssetServer::load`
+pub fn asset_path(path: &str) -> PathBuf {
+  asset_root().join(path)
+}
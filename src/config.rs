@@ -1,57 +1,249 @@
 use crate::consts::*;
+use crate::display::Monitors;
+use crate::input::{InputAction, InputBinding};
 use crate::settings::{SettingItem, SettingType};
+use crate::sounds::AudioVolumes;
+use bevy::input::gamepad::GamepadButtonType;
 use bevy::input::keyboard::KeyCode;
 use bevy::prelude::*;
 use bevy::window::WindowMode;
-use serde::{Deserialize, Serialize};
-use std::fs::{read, write};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs::{create_dir_all, read, write};
+use std::path::PathBuf;
 
+/// `KeyBindings` used to store a plain `MouseButton` or `KeyCode`,
+/// accept both of them along with the tagged `InputBinding`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegacyBinding<T> {
+  Binding(InputBinding),
+  Legacy(T),
+}
+
+fn deserialize_mouse<'de, D: Deserializer<'de>>(deserializer: D) -> Result<InputBinding, D::Error> {
+  Ok(match LegacyBinding::<MouseButton>::deserialize(deserializer)? {
+    LegacyBinding::Binding(binding) => binding,
+    LegacyBinding::Legacy(button) => InputBinding::Mouse(button),
+  })
+}
+
+fn deserialize_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<InputBinding, D::Error> {
+  Ok(match LegacyBinding::<KeyCode>::deserialize(deserializer)? {
+    LegacyBinding::Binding(binding) => binding,
+    LegacyBinding::Legacy(key) => InputBinding::Key(key),
+  })
+}
+
+/// Gamepad buttons of actions, unbound actions are keyboard / mouse only
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadBindings {
+  attack_1: Option<GamepadButtonType>,
+  attack_2: Option<GamepadButtonType>,
+  special_status_launch: Option<GamepadButtonType>,
+  assault: Option<GamepadButtonType>,
+  switch_weapon: Option<GamepadButtonType>,
+  interact: Option<GamepadButtonType>,
+  start_mahou: Option<GamepadButtonType>,
+  item_1: Option<GamepadButtonType>,
+  item_2: Option<GamepadButtonType>,
+  item_3: Option<GamepadButtonType>,
+  item_4: Option<GamepadButtonType>,
+}
+
+impl Default for GamepadBindings {
+  fn default() -> Self {
+    GamepadBindings {
+      attack_1: Some(GamepadButtonType::West),
+      attack_2: Some(GamepadButtonType::North),
+      special_status_launch: Some(GamepadButtonType::LeftTrigger),
+      assault: Some(GamepadButtonType::South),
+      switch_weapon: Some(GamepadButtonType::RightTrigger),
+      interact: Some(GamepadButtonType::East),
+      start_mahou: Some(GamepadButtonType::LeftTrigger2),
+      item_1: Some(GamepadButtonType::DPadUp),
+      item_2: Some(GamepadButtonType::DPadRight),
+      item_3: Some(GamepadButtonType::DPadDown),
+      item_4: Some(GamepadButtonType::DPadLeft),
+    }
+  }
+}
+
+impl GamepadBindings {
+  #[rustfmt::skip]
+  pub fn get(&self, action: InputAction) -> Option<GamepadButtonType> {
+    use InputAction::*;
+    match action {
+      Attack1 =>             self.attack_1,
+      Attack2 =>             self.attack_2,
+      SpecialStatusLaunch => self.special_status_launch,
+      Assault =>             self.assault,
+      SwitchWeapon =>        self.switch_weapon,
+      Interact =>            self.interact,
+      StartMahou =>          self.start_mahou,
+      Item1 =>               self.item_1,
+      Item2 =>               self.item_2,
+      Item3 =>               self.item_3,
+      Item4 =>               self.item_4,
+      _ =>                   None,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct KeyBindings {
-  attack_1: MouseButton,
-  attack_2: MouseButton,
-  special_status_launch: KeyCode,
-  assault: KeyCode,
-  move_upwards: KeyCode,
-  move_downwards: KeyCode,
-  move_leftwards: KeyCode,
-  move_rightwards: KeyCode,
-  interact: KeyCode,
-  open_handbook: KeyCode,
-  open_plugins: KeyCode,
-  start_mahou: KeyCode,
-  item_1: KeyCode,
-  item_2: KeyCode,
-  item_3: KeyCode,
-  item_4: KeyCode,
+  #[serde(deserialize_with = "deserialize_mouse")]
+  attack_1: InputBinding,
+  #[serde(deserialize_with = "deserialize_mouse")]
+  attack_2: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  special_status_launch: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  assault: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  switch_weapon: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  move_upwards: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  move_downwards: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  move_leftwards: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  move_rightwards: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  interact: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  open_handbook: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  open_plugins: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  start_mahou: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  item_1: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  item_2: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  item_3: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  item_4: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  quick_save: InputBinding,
+  #[serde(deserialize_with = "deserialize_key")]
+  quick_load: InputBinding,
+  pub gamepad: GamepadBindings,
 }
 
 impl Default for KeyBindings {
   fn default() -> Self {
     KeyBindings {
-      attack_1: MouseButton::Left,
-      attack_2: MouseButton::Right,
-      special_status_launch: KeyCode::Q,
-      assault: KeyCode::Space,
-      move_upwards: KeyCode::W,
-      move_downwards: KeyCode::S,
-      move_leftwards: KeyCode::A,
-      move_rightwards: KeyCode::D,
-      interact: KeyCode::E,
-      open_handbook: KeyCode::H,
-      open_plugins: KeyCode::P,
-      start_mahou: KeyCode::LAlt,
-      item_1: KeyCode::Key1,
-      item_2: KeyCode::Key2,
-      item_3: KeyCode::Key3,
-      item_4: KeyCode::Key4,
-    }
-  }
-}
-
-/// Game config
+      attack_1: InputBinding::Mouse(MouseButton::Left),
+      attack_2: InputBinding::Mouse(MouseButton::Right),
+      special_status_launch: InputBinding::Key(KeyCode::Q),
+      assault: InputBinding::Key(KeyCode::Space),
+      switch_weapon: InputBinding::Key(KeyCode::Tab),
+      move_upwards: InputBinding::Key(KeyCode::W),
+      move_downwards: InputBinding::Key(KeyCode::S),
+      move_leftwards: InputBinding::Key(KeyCode::A),
+      move_rightwards: InputBinding::Key(KeyCode::D),
+      interact: InputBinding::Key(KeyCode::E),
+      open_handbook: InputBinding::Key(KeyCode::H),
+      open_plugins: InputBinding::Key(KeyCode::P),
+      start_mahou: InputBinding::Key(KeyCode::LAlt),
+      item_1: InputBinding::Key(KeyCode::Key1),
+      item_2: InputBinding::Key(KeyCode::Key2),
+      item_3: InputBinding::Key(KeyCode::Key3),
+      item_4: InputBinding::Key(KeyCode::Key4),
+      quick_save: InputBinding::Key(KeyCode::F5),
+      quick_load: InputBinding::Key(KeyCode::F9),
+      gamepad: GamepadBindings::default(),
+    }
+  }
+}
+
+impl KeyBindings {
+  #[rustfmt::skip]
+  pub fn get(&self, action: InputAction) -> InputBinding {
+    use InputAction::*;
+    match action {
+      Attack1 =>             self.attack_1,
+      Attack2 =>             self.attack_2,
+      SpecialStatusLaunch => self.special_status_launch,
+      Assault =>             self.assault,
+      SwitchWeapon =>        self.switch_weapon,
+      MoveUpwards =>         self.move_upwards,
+      MoveDownwards =>       self.move_downwards,
+      MoveLeftwards =>       self.move_leftwards,
+      MoveRightwards =>      self.move_rightwards,
+      Interact =>            self.interact,
+      OpenHandbook =>        self.open_handbook,
+      OpenPlugins =>         self.open_plugins,
+      StartMahou =>          self.start_mahou,
+      Item1 =>               self.item_1,
+      Item2 =>               self.item_2,
+      Item3 =>               self.item_3,
+      Item4 =>               self.item_4,
+      QuickSave =>           self.quick_save,
+      QuickLoad =>           self.quick_load,
+    }
+  }
+
+  #[rustfmt::skip]
+  pub fn set(&mut self, action: InputAction, binding: InputBinding) {
+    use InputAction::*;
+    let field = match action {
+      Attack1 =>             &mut self.attack_1,
+      Attack2 =>             &mut self.attack_2,
+      SpecialStatusLaunch => &mut self.special_status_launch,
+      Assault =>             &mut self.assault,
+      SwitchWeapon =>        &mut self.switch_weapon,
+      MoveUpwards =>         &mut self.move_upwards,
+      MoveDownwards =>       &mut self.move_downwards,
+      MoveLeftwards =>       &mut self.move_leftwards,
+      MoveRightwards =>      &mut self.move_rightwards,
+      Interact =>            &mut self.interact,
+      OpenHandbook =>        &mut self.open_handbook,
+      OpenPlugins =>         &mut self.open_plugins,
+      StartMahou =>          &mut self.start_mahou,
+      Item1 =>               &mut self.item_1,
+      Item2 =>               &mut self.item_2,
+      Item3 =>               &mut self.item_3,
+      Item4 =>               &mut self.item_4,
+      QuickSave =>           &mut self.quick_save,
+      QuickLoad =>           &mut self.quick_load,
+    };
+    *field = binding;
+  }
+}
+
+/// parse resolutions shown as `1920x1080`
+fn parse_resolution(text: &str) -> Option<(u32, u32)> {
+  let mut parts = text.split('x');
+  let width = parts.next()?.parse().ok()?;
+  let height = parts.next()?.parse().ok()?;
+  Some((width, height))
+}
+
+/// Settings applied to the window, kept to revert unconfirmed changes
+#[derive(Clone, PartialEq)]
+pub struct DisplaySettings {
+  fullscreen: bool,
+  borderless: bool,
+  monitor: usize,
+  resolution: (u32, u32),
+  decorations: bool,
+  vsync: bool,
+  ui_scale: f32,
+}
+
+/// Game config, missing fields are filled with the default config
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameConfig {
+  /// schema version, see `migrate_config`
+  version: u32,
+
   volume: f32,
   volume_music: f32,
   volume_sfx: f32,
@@ -59,15 +251,78 @@ pub struct GameConfig {
   save_location: String,
 
   fullscreen: bool,
-  resolution: usize,
+  /// borderless or exclusive fullscreen
+  borderless: bool,
+  /// index of the monitor in `Monitors`
+  monitor: usize,
+  resolution: (u32, u32),
   decorations: bool,
+  vsync: bool,
+  /// 0 for unlimited
+  fps_limit: u32,
+  ui_scale: f32,
 
   attack_to_mouse: bool,
   assault_to_mouse: bool,
   mouse_sensitivity: f32,
+  gamepad_dead_zone: f32,
+  gamepad_sensitivity: f32,
   key_bindings: KeyBindings,
 }
 
+/// upgrade a config written by older versions to `CONFIG_VERSION`
+fn migrate_config(config: &mut toml::value::Table) {
+  let version = config
+    .get("version")
+    .and_then(|version| version.as_integer())
+    .unwrap_or(0);
+
+  if version < 1 {
+    // `resolution` was an index of `RESOLUTION_LIST`
+    if let Some(index) = config.get("resolution").and_then(|index| index.as_integer()) {
+      let (width, height) = RESOLUTION_LIST[index as usize % RESOLUTION_LIST.len()];
+      config.insert(
+        "resolution".to_string(),
+        toml::Value::Array(vec![i64::from(width).into(), i64::from(height).into()]),
+      );
+    }
+  }
+
+  config.insert("version".to_string(), i64::from(CONFIG_VERSION).into());
+}
+
+/// drop the fields of `table` which `T` can not read, checked one by one.
+/// `path` names the table in warnings
+fn drop_invalid_fields<T: DeserializeOwned>(table: &mut toml::value::Table, path: &str) {
+  let keys: Vec<String> = table.keys().cloned().collect();
+  for key in keys {
+    let mut field = toml::value::Table::new();
+    field.insert(key.clone(), table[&key].clone());
+    if toml::Value::Table(field).try_into::<T>().is_err() {
+      warn!("invalid config field {}{}, use the default value", path, key);
+      table.remove(&key);
+    }
+  }
+}
+
+/// directory of the config file, following the conventions of each platform
+fn config_dir() -> PathBuf {
+  #[cfg(target_os = "windows")]
+  let base = std::env::var_os("APPDATA").map(PathBuf::from);
+  #[cfg(target_os = "macos")]
+  let base = home::home_dir().map(|home| home.join("Library").join("Application Support"));
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  let base = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .filter(|dir| dir.is_absolute())
+    .or_else(|| home::home_dir().map(|home| home.join(".config")));
+
+  base
+    .unwrap_or_else(|| PathBuf::from("."))
+    .join("kuzumajo")
+    .join("upstream")
+}
+
 impl Default for GameConfig {
   fn default() -> Self {
     let save_dir = home::home_dir()
@@ -79,6 +334,8 @@ impl Default for GameConfig {
       .to_string();
 
     GameConfig {
+      version: CONFIG_VERSION,
+
       volume: 1.0,
       volume_music: 1.0,
       volume_sfx: 1.0,
@@ -86,62 +343,257 @@ impl Default for GameConfig {
       save_location: save_dir,
 
       fullscreen: false,
-      resolution: 0,
+      borderless: false,
+      monitor: 0,
+      resolution: RESOLUTION_LIST[0],
       decorations: true,
+      vsync: true,
+      fps_limit: 0,
+      ui_scale: 1.0,
 
       attack_to_mouse: true,
       assault_to_mouse: true,
       mouse_sensitivity: 1.0,
+      gamepad_dead_zone: 0.15,
+      gamepad_sensitivity: 0.5,
       key_bindings: KeyBindings::default(),
     }
   }
 }
 
 impl GameConfig {
-  const CONFIG_FILE: &'static str = "display.cfg";
+  const CONFIG_FILE: &'static str = "config.toml";
+  /// config file of older versions, placed in the working directory
+  const LEGACY_CONFIG_FILE: &'static str = "display.cfg";
 
-  /// load from disk or generate a default one
+  pub fn config_file() -> PathBuf {
+    config_dir().join(GameConfig::CONFIG_FILE)
+  }
+
+  /// parse a config of any version, invalid fields are dropped
+  fn parse(data: &[u8]) -> Result<GameConfig, toml::de::Error> {
+    let mut config = toml::from_slice::<toml::value::Table>(data)?;
+    migrate_config(&mut config);
+
+    // innermost tables first, a single bad binding keeps the others
+    if let Some(toml::Value::Table(bindings)) = config.get_mut("key_bindings") {
+      if let Some(toml::Value::Table(gamepad)) = bindings.get_mut("gamepad") {
+        drop_invalid_fields::<GamepadBindings>(gamepad, "key_bindings.gamepad.");
+      }
+      drop_invalid_fields::<KeyBindings>(bindings, "key_bindings.");
+    }
+    drop_invalid_fields::<GameConfig>(&mut config, "");
+    toml::Value::Table(config).try_into()
+  }
+
+  /// load from disk or generate a default one,
+  /// the legacy `display.cfg` is imported if there is no config yet
   pub fn load() -> GameConfig {
-    if let Ok(data) = read(GameConfig::CONFIG_FILE) {
-      if let Ok(config) = toml::from_slice::<GameConfig>(&data[..]) {
-        return config;
+    let path = GameConfig::config_file();
+    if let Ok(data) = read(&path) {
+      match GameConfig::parse(&data[..]) {
+        Ok(config) => return config,
+        Err(err) => warn!("failed to parse config {}: {}", path.display(), err),
+      }
+    } else if let Ok(data) = read(GameConfig::LEGACY_CONFIG_FILE) {
+      match GameConfig::parse(&data[..]) {
+        Ok(config) => {
+          match config.save() {
+            Ok(()) => info!("imported config from {}", GameConfig::LEGACY_CONFIG_FILE),
+            Err(err) => warn!("failed to import config: {}", err),
+          }
+          return config;
+        }
+        Err(err) => warn!("failed to parse config {}: {}", GameConfig::LEGACY_CONFIG_FILE, err),
       }
     }
-    warn!("failed to read display config from disk, use the default config");
+    warn!("use the default config");
     GameConfig::default()
   }
 
   /// save to disk
   pub fn save(&self) -> std::io::Result<()> {
-    write(GameConfig::CONFIG_FILE, toml::to_vec(self).unwrap())?;
-    info!("display config saved to {}", GameConfig::CONFIG_FILE);
+    let path = GameConfig::config_file();
+    if let Some(dir) = path.parent() {
+      create_dir_all(dir)?;
+    }
+    write(&path, toml::to_vec(self).unwrap())?;
+    info!("config saved to {}", path.display());
     Ok(())
   }
+
+  /// save with `display` in place of the current display settings,
+  /// keeps unconfirmed display changes off the disk
+  pub fn save_with_display(&mut self, display: DisplaySettings) -> std::io::Result<()> {
+    let current = self.display_settings();
+    self.set_display_settings(display);
+    let result = self.save();
+    self.set_display_settings(current);
+    result
+  }
+
+  pub fn key_bindings(&self) -> &KeyBindings {
+    &self.key_bindings
+  }
+
+  /// sticks tilted less than this are ignored
+  pub fn gamepad_dead_zone(&self) -> f32 {
+    self.gamepad_dead_zone
+  }
+
+  /// multiplier of stick tilt, the slider maps to `0.0..=GAMEPAD_SENSITIVITY_MAX`
+  pub fn gamepad_sensitivity(&self) -> f32 {
+    self.gamepad_sensitivity * GAMEPAD_SENSITIVITY_MAX
+  }
+
+  pub fn audio_volumes(&self) -> AudioVolumes {
+    AudioVolumes {
+      master: self.volume,
+      music: self.volume_music,
+      sfx: self.volume_sfx,
+      voice: self.volume_voice,
+    }
+  }
+
+  /// attack towards the mouse, or the facing direction
+  pub fn attack_to_mouse(&self) -> bool {
+    self.attack_to_mouse
+  }
+
+  /// assault towards the mouse, or the facing direction
+  pub fn assault_to_mouse(&self) -> bool {
+    self.assault_to_mouse
+  }
+
+  /// how fast the aim follows the mouse
+  pub fn mouse_sensitivity(&self) -> f32 {
+    self.mouse_sensitivity.max(MOUSE_SENSITIVITY_MIN)
+  }
+
+  /// frame rate limit, `None` for unlimited
+  pub fn fps_limit(&self) -> Option<u32> {
+    match self.fps_limit {
+      0 => None,
+      fps => Some(fps),
+    }
+  }
+
+  /// directory where game saves are stored
+  pub fn save_dir(&self) -> PathBuf {
+    PathBuf::from(&self.save_location)
+  }
 }
 
 impl GameConfig {
+  pub fn display_settings(&self) -> DisplaySettings {
+    DisplaySettings {
+      fullscreen: self.fullscreen,
+      borderless: self.borderless,
+      monitor: self.monitor,
+      resolution: self.resolution,
+      decorations: self.decorations,
+      vsync: self.vsync,
+      ui_scale: self.ui_scale,
+    }
+  }
+
+  pub fn set_display_settings(&mut self, display: DisplaySettings) {
+    self.fullscreen = display.fullscreen;
+    self.borderless = display.borderless;
+    self.monitor = display.monitor;
+    self.resolution = display.resolution;
+    self.decorations = display.decorations;
+    self.vsync = display.vsync;
+    self.ui_scale = display.ui_scale;
+  }
+
+  /// apply display settings to the running window
+  pub fn apply_to_window(&self, window: &mut Window, monitors: &Monitors) {
+    let descriptor = self.get_window_descriptor();
+    // move to the center of the monitor, fullscreen follows the monitor of the window
+    if let Some(monitor) = monitors.get(self.monitor) {
+      let (width, height) = self.resolution;
+      let offset = IVec2::new(
+        (monitor.size.0 as i32 - width as i32).max(0) / 2,
+        (monitor.size.1 as i32 - height as i32).max(0) / 2,
+      );
+      window.set_position(monitor.position + offset);
+    }
+    window.set_scale_factor_override(descriptor.scale_factor_override);
+    window.set_vsync(descriptor.vsync);
+    window.set_mode(descriptor.mode);
+    window.set_decorations(descriptor.decorations);
+    window.set_resolution(descriptor.width, descriptor.height);
+  }
+
   pub fn get_window_descriptor(&self) -> WindowDescriptor {
-    let (width, height) = RESOLUTION_LIST[self.resolution as usize % RESOLUTION_LIST.len()];
+    let (width, height) = self.resolution;
 
     WindowDescriptor {
-      width: width as f32,
-      height: height as f32,
+      // logical size, the physical size is the resolution
+      width: width as f32 / self.ui_scale,
+      height: height as f32 / self.ui_scale,
       resizable: true,
       title: "Upstream".to_string(),
-      vsync: true,
+      vsync: self.vsync,
       decorations: self.decorations,
-      mode: if self.fullscreen {
-        WindowMode::Fullscreen { use_size: false }
-      } else {
-        WindowMode::Windowed
+      mode: match (self.fullscreen, self.borderless) {
+        (false, _) => WindowMode::Windowed,
+        (true, true) => WindowMode::BorderlessFullscreen,
+        (true, false) => WindowMode::Fullscreen { use_size: true },
       },
-      scale_factor_override: Some(1.0),
+      scale_factor_override: Some(self.ui_scale as f64),
       ..Default::default()
     }
   }
 
+  fn monitor_options(&self, monitors: &Monitors) -> SettingType {
+    let mut names: Vec<String> = monitors.0.iter().map(|monitor| monitor.name.clone()).collect();
+    if names.is_empty() {
+      names.push("Monitor 1".to_string());
+    }
+    SettingType::Select(self.monitor.min(names.len() - 1), names)
+  }
+
+  fn resolution_options(&self, monitors: &Monitors) -> SettingType {
+    let mut resolutions = monitors.resolutions(self.monitor);
+    if !resolutions.contains(&self.resolution) {
+      resolutions.push(self.resolution);
+    }
+    let selected = resolutions.iter().position(|r| *r == self.resolution).unwrap();
+    SettingType::Select(
+      selected,
+      resolutions.iter().map(|(w, h)| format!("{}x{}", w, h)).collect(),
+    )
+  }
+
+  fn fps_limit_options(&self) -> SettingType {
+    let selected = FPS_LIMIT_LIST.iter().position(|fps| *fps == self.fps_limit).unwrap_or(0);
+    SettingType::Select(
+      selected,
+      FPS_LIMIT_LIST
+        .iter()
+        .map(|fps| match fps {
+          0 => "無制限".to_string(),
+          fps => fps.to_string(),
+        })
+        .collect(),
+    )
+  }
+
+  fn ui_scale_options(&self) -> SettingType {
+    let selected = UI_SCALE_LIST
+      .iter()
+      .position(|scale| (scale - self.ui_scale).abs() < 0.01)
+      .unwrap_or(1);
+    SettingType::Select(
+      selected,
+      UI_SCALE_LIST.iter().map(|scale| format!("{}%", scale * 100.0)).collect(),
+    )
+  }
+
   #[rustfmt::skip]
-  pub fn get_settings_type(&self, item: &SettingItem) -> SettingType {
+  pub fn get_settings_type(&self, item: &SettingItem, monitors: &Monitors) -> SettingType {
     use SettingItem::*;
     use SettingType::*;
     match *item {
@@ -151,11 +603,19 @@ impl GameConfig {
       VolumeVoice =>      Slide(self.volume_voice),
       SaveDir =>          String(self.save_location.clone()),
       Fullscreen =>       Ratio(self.fullscreen),
-      Resolution =>       Select(self.resolution, RESOLUTION_LIST.iter().map(|(w, h)| format!("{}x{}", w, h)).collect()),
+      Borderless =>       Ratio(self.borderless),
+      Monitor =>          self.monitor_options(monitors),
+      Resolution =>       self.resolution_options(monitors),
       Decorations =>      Ratio(self.decorations),
+      VSync =>            Ratio(self.vsync),
+      FpsLimit =>         self.fps_limit_options(),
+      UiScale =>          self.ui_scale_options(),
       AttackToMouse =>    Ratio(self.attack_to_mouse),
       AssaultToMouse =>   Ratio(self.assault_to_mouse),
       MouseSensitivity => Slide(self.mouse_sensitivity),
+      GamepadDeadZone =>  Slide(self.gamepad_dead_zone),
+      GamepadSensitivity => Slide(self.gamepad_sensitivity),
+      KeyBinding(action) => Binding(self.key_bindings.get(action)),
     }
   }
 
@@ -170,11 +630,23 @@ impl GameConfig {
       VolumeVoice      => if let Slide(value) = *stype     { self.volume_voice = value;          },
       SaveDir          => if let String(value) = &*stype   { self.save_location = value.clone(); },
       Fullscreen       => if let Ratio(value) = *stype     { self.fullscreen = value;            },
-      Resolution       => if let Select(value, _) = *stype { self.resolution = value;            },
+      Borderless       => if let Ratio(value) = *stype     { self.borderless = value;            },
+      Monitor          => if let Select(value, _) = *stype { self.monitor = value;               },
+      Resolution       => if let Select(value, list) = &*stype {
+        if let Some(resolution) = parse_resolution(&list[*value]) {
+          self.resolution = resolution;
+        }
+      },
       Decorations      => if let Ratio(value) = *stype     { self.decorations = value;           },
+      VSync            => if let Ratio(value) = *stype     { self.vsync = value;                 },
+      FpsLimit         => if let Select(value, _) = *stype { self.fps_limit = FPS_LIMIT_LIST[value]; },
+      UiScale          => if let Select(value, _) = *stype { self.ui_scale = UI_SCALE_LIST[value]; },
       AttackToMouse    => if let Ratio(value) = *stype     { self.attack_to_mouse = value;       },
       AssaultToMouse   => if let Ratio(value) = *stype     { self.assault_to_mouse = value;      },
       MouseSensitivity => if let Slide(value) = *stype     { self.mouse_sensitivity = value;     },
+      GamepadDeadZone  => if let Slide(value) = *stype     { self.gamepad_dead_zone = value;     },
+      GamepadSensitivity => if let Slide(value) = *stype   { self.gamepad_sensitivity = value;   },
+      KeyBinding(action) => if let Binding(value) = *stype { self.key_bindings.set(action, value);   },
     }
   }
 }
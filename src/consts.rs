@@ -30,23 +30,60 @@ pub const ENERGY_BAR_WIDTH: f32 = 300.0;
 pub const STAFF_LIST_WAITING_SECONDS: f32 = 2.0;
 
 // settings
+/// resolutions used when monitors can not be queried
 pub const RESOLUTION_LIST: [(u32, u32); 2] = [(1280, 720), (1920, 1080)];
+/// options of the frame rate limit, 0 for unlimited
+pub const FPS_LIMIT_LIST: [u32; 6] = [0, 30, 60, 120, 144, 240];
+pub const UI_SCALE_LIST: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
 pub const SLIDER_LENGTH: f32 = 500.0;
+/// seconds to confirm new display settings before they are reverted
+pub const DISPLAY_REVERT_SECONDS: f32 = 15.0;
+/// gamepad sensitivity at the right end of its slider
+pub const GAMEPAD_SENSITIVITY_MAX: f32 = 2.0;
+/// schema version of the config file
+pub const CONFIG_VERSION: u32 = 1;
+/// lowest mouse sensitivity, so that the aim never stops following
+pub const MOUSE_SENSITIVITY_MIN: f32 = 0.05;
+/// how fast the aim follows the mouse at half sensitivity, full sensitivity has no smoothing
+pub const AIM_FOLLOW_SPEED: f32 = 60.0;
+
+// load game
+pub const SAVE_SLOT_HEIGHT: f32 = 120.0;
 
 // game
 pub const GAME_AUTOSAVE_INTERVAL: f32 = 60.0;
+/// how many previous writes of each slot are kept as backups
+pub const SAVE_BACKUP_COUNT: usize = 3;
+/// unreadable saves are moved into this sub directory
+pub const SAVE_QUARANTINE_DIR: &str = "broken";
+/// seconds to show the quick save / load notice
+pub const QUICK_SAVE_NOTICE_TIME: f32 = 2.0;
 
 pub const PLAYER_MOVE_SPEED: f32 = 200.0;
-pub const PLAYER_SHIELD_BULLET_SPEED: f32 = 1800.0;
 
 pub const CAMERA_SYNC_SPEED: f32 = 6.0;
 
-// Shield
-pub const SHIELD_ASSAULT_SPEED: f32 = 900.0;
+// audio
+/// seconds a sound may wait for its file, late sounds no longer match the screen
+pub const SOUND_MAX_QUEUED_SECONDS: f32 = 2.0;
+
+// Damage
+/// seconds without attacks before a hit group is forgotten
+pub const HIT_GROUP_EXPIRE_SECONDS: f32 = 1.0;
+/// knockback speed of every point of power over the poise
+pub const KNOCKBACK_SPEED: f32 = 400.0;
+pub const KNOCKBACK_SECONDS: f32 = 0.2;
+pub const STAGGER_SECONDS: f32 = 0.4;
 
 // crypto
 
-/// Crypto key, but maybe change after release
+/// Crypto key of legacy saves, new saves use a key per installation
 pub const CRYPTO_KEY: &str = "flag{dHrkWcgkdohUvl4IywOGpPhlHz}";
 
+/// secret of this installation, placed in `~/.kuzumajo`
+pub const INSTALL_KEY_FILE: &str = "install.key";
+
 pub const PLAYER_NAME: &str = "蓿";
+
+/// area of a new game
+pub const START_AREA: &str = "序章";
@@ -1,19 +1,112 @@
+use crate::consts::*;
+use bevy::prelude::*;
 use magic_crypt::MagicCrypt256;
 use magic_crypt::MagicCryptError;
 use magic_crypt::MagicCryptTrait;
+use sha2::Digest;
+use sha2::Sha256;
+use std::fmt;
+use std::fs::create_dir_all;
+use std::fs::read_to_string;
+use std::fs::write;
+use std::io::ErrorKind;
+use std::path::PathBuf;
 
-/// encrypt data
+/// encrypt and authenticate data
 pub struct Crypto {
   cipher: MagicCrypt256,
+  mac_key: [u8; 32],
+  /// saves written before per-installation keys
+  legacy_cipher: MagicCrypt256,
+}
+
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+  let mut hasher = Sha256::new();
+  for part in parts {
+    hasher.update(part);
+  }
+  let mut hash = [0u8; 32];
+  hash.copy_from_slice(&hasher.finalize());
+  hash
+}
+
+fn to_hex(data: &[u8]) -> String {
+  data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+  if text.len() % 2 != 0 {
+    return None;
+  }
+  (0..text.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+    .collect()
+}
+
+/// Reasons why the installation key can not be used.
+/// Saves are neither read nor written without it,
+/// any other key would make every save look tampered.
+#[derive(Debug)]
+pub enum InstallKeyError {
+  /// no home directory to keep the key in
+  NoHome,
+  /// unable to read or write the key file
+  Io(PathBuf, std::io::Error),
+  /// the key file is not a hex string
+  Malformed(PathBuf),
+  /// the system random source failed
+  Random,
+}
+
+impl fmt::Display for InstallKeyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      InstallKeyError::NoHome => write!(f, "no home directory for the installation key"),
+      InstallKeyError::Io(path, err) => write!(f, "installation key {:?}: {}", path, err),
+      InstallKeyError::Malformed(path) => write!(f, "installation key {:?} is broken", path),
+      InstallKeyError::Random => write!(f, "unable to generate the installation key"),
+    }
+  }
+}
+
+fn get_install_key_path() -> Option<PathBuf> {
+  Some(home::home_dir()?.join(".kuzumajo").join(INSTALL_KEY_FILE))
+}
+
+/// read the secret of this installation, or generate one at the first launch
+fn load_install_secret() -> Result<Vec<u8>, InstallKeyError> {
+  let path = get_install_key_path().ok_or(InstallKeyError::NoHome)?;
+  match read_to_string(&path) {
+    Ok(text) => return from_hex(text.trim()).ok_or(InstallKeyError::Malformed(path)),
+    Err(err) if err.kind() != ErrorKind::NotFound => return Err(InstallKeyError::Io(path, err)),
+    Err(_) => {}
+  }
+
+  let mut secret = [0u8; 32];
+  getrandom::getrandom(&mut secret).map_err(|_| InstallKeyError::Random)?;
+  if let Some(dir) = path.parent() {
+    create_dir_all(dir).map_err(|err| InstallKeyError::Io(path.clone(), err))?;
+  }
+  write(&path, to_hex(&secret)).map_err(|err| InstallKeyError::Io(path.clone(), err))?;
+  info!("installation key generated at {:?}", path);
+  Ok(secret.to_vec())
 }
 
 impl Crypto {
-  pub fn new(key: &str) -> Self {
+  pub fn new(secret: &[u8]) -> Self {
     Crypto {
-      cipher: new_magic_crypt!(key, 256),
+      cipher: new_magic_crypt!(to_hex(&sha256(&[b"upstream-encrypt", secret])), 256),
+      mac_key: sha256(&[b"upstream-mac", secret]),
+      legacy_cipher: new_magic_crypt!(CRYPTO_KEY, 256),
     }
   }
 
+  /// crypto with the key of this installation
+  pub fn from_installation() -> Result<Self, InstallKeyError> {
+    load_install_secret().map(|secret| Crypto::new(&secret))
+  }
+
   pub fn encrypt(&self, data: &[u8]) -> Vec<u8> {
     self.cipher.encrypt_bytes_to_bytes(data)
   }
@@ -21,4 +114,32 @@ impl Crypto {
   pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, MagicCryptError> {
     self.cipher.decrypt_bytes_to_bytes(data)
   }
+
+  pub fn decrypt_legacy(&self, data: &[u8]) -> Result<Vec<u8>, MagicCryptError> {
+    self.legacy_cipher.decrypt_bytes_to_bytes(data)
+  }
+
+  /// plain sha256, anyone can compute it, used to detect corruption
+  pub fn digest(data: &[u8]) -> [u8; 32] {
+    sha256(&[data])
+  }
+
+  /// HMAC-SHA256 with the key of this installation, used to detect tampering
+  pub fn sign(&self, data: &[u8]) -> [u8; 32] {
+    let mut inner_key = [0x36u8; 64];
+    let mut outer_key = [0x5cu8; 64];
+    for (i, byte) in self.mac_key.iter().enumerate() {
+      inner_key[i] ^= byte;
+      outer_key[i] ^= byte;
+    }
+    let inner = sha256(&[&inner_key, data]);
+    sha256(&[&outer_key, &inner])
+  }
+
+  pub fn verify(&self, data: &[u8], mac: &[u8]) -> bool {
+    let expected = self.sign(data);
+    // constant time comparison
+    expected.len() == mac.len()
+      && expected.iter().zip(mac.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+  }
 }
@@ -0,0 +1,126 @@
+use crate::config::GameConfig;
+use crate::consts::*;
+use crate::WindowSize;
+use bevy::ecs::system::NonSend;
+use bevy::prelude::*;
+use bevy::winit::WinitWindows;
+use std::time::{Duration, Instant};
+
+/// A monitor connected to the computer
+pub struct MonitorInfo {
+  pub name: String,
+  /// top left corner, in physical pixels
+  pub position: IVec2,
+  /// in physical pixels
+  pub size: (u32, u32),
+  /// sizes of video modes, from large to small
+  pub resolutions: Vec<(u32, u32)>,
+}
+
+/// Monitors of the computer, refreshed when entering settings
+#[derive(Default)]
+pub struct Monitors(pub Vec<MonitorInfo>);
+
+impl Monitors {
+  pub fn get(&self, index: usize) -> Option<&MonitorInfo> {
+    self.0.get(index)
+  }
+
+  /// resolutions of a monitor, `RESOLUTION_LIST` if it is unknown
+  pub fn resolutions(&self, index: usize) -> Vec<(u32, u32)> {
+    match self.get(index) {
+      Some(monitor) if !monitor.resolutions.is_empty() => monitor.resolutions.clone(),
+      _ => RESOLUTION_LIST.to_vec(),
+    }
+  }
+}
+
+fn read_monitors(window: &winit::window::Window) -> Vec<MonitorInfo> {
+  window
+    .available_monitors()
+    .enumerate()
+    .map(|(index, monitor)| {
+      let mut resolutions: Vec<(u32, u32)> = monitor
+        .video_modes()
+        .map(|mode| (mode.size().width, mode.size().height))
+        .collect();
+      resolutions.sort_by(|a, b| b.cmp(a));
+      resolutions.dedup();
+
+      MonitorInfo {
+        name: monitor
+          .name()
+          .unwrap_or_else(|| format!("Monitor {}", index + 1)),
+        position: IVec2::new(monitor.position().x, monitor.position().y),
+        size: (monitor.size().width, monitor.size().height),
+        resolutions,
+      }
+    })
+    .collect()
+}
+
+/// read monitors and their video modes from the primary window
+pub fn refresh_monitors(
+  windows: Res<Windows>,
+  winit_windows: NonSend<WinitWindows>,
+  mut monitors: ResMut<Monitors>,
+) {
+  if let Some(window) = windows
+    .get_primary()
+    .and_then(|window| winit_windows.get_window(window.id()))
+  {
+    monitors.0 = read_monitors(window);
+  }
+}
+
+/// monitor choice needs the window to exist, apply once it is created
+fn apply_display_on_startup(
+  mut applied: Local<bool>,
+  config: Res<GameConfig>,
+  mut windows: ResMut<Windows>,
+  winit_windows: NonSend<WinitWindows>,
+  mut monitors: ResMut<Monitors>,
+  mut window_size: ResMut<WindowSize>,
+) {
+  if *applied {
+    return;
+  }
+  let window = match windows.get_primary_mut() {
+    Some(window) => window,
+    None => return,
+  };
+  let winit_window = match winit_windows.get_window(window.id()) {
+    Some(winit_window) => winit_window,
+    None => return,
+  };
+  *applied = true;
+
+  monitors.0 = read_monitors(winit_window);
+  config.apply_to_window(window, &monitors);
+  window_size.width = window.width();
+  window_size.height = window.height();
+}
+
+/// sleep at the end of frames to keep under the frame rate limit
+fn limit_frame_rate(config: Res<GameConfig>, mut last_frame: Local<Option<Instant>>) {
+  if let (Some(fps), Some(last_frame)) = (config.fps_limit(), *last_frame) {
+    let frame_time = Duration::from_secs_f64(1.0 / fps as f64);
+    let elapsed = last_frame.elapsed();
+    if elapsed < frame_time {
+      std::thread::sleep(frame_time - elapsed);
+    }
+  }
+  *last_frame = Some(Instant::now());
+}
+
+/// Monitors, display settings and frame rate limit
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Monitors>()
+      .add_system(apply_display_on_startup)
+      .add_system_to_stage(CoreStage::Last, limit_frame_rate);
+  }
+}
@@ -3,6 +3,7 @@ use bevy::{app::PluginGroupBuilder, prelude::*};
 mod control_panel;
 mod game_saves;
 mod camera;
+mod sfx;
 mod stages;
 
 mod engine;
@@ -12,6 +13,7 @@ mod entity;
 use control_panel::ControlPanelPlugin;
 use game_saves::GameSavePlugin;
 use camera::CameraPlugin;
+use sfx::CombatSoundPlugin;
 
 pub use game_saves::AutoSaveSlot;
 pub use camera::GameCamera;
@@ -25,9 +27,16 @@ impl PluginGroup for GameBasicPlugins {
     app
       .add(CameraPlugin)
       .add(ControlPanelPlugin)
-      .add(GameSavePlugin);
+      .add(GameSavePlugin)
+      .add(CombatSoundPlugin);
   }
 }
 
 pub use engine::GameSystemPlugins;
+pub use engine::snapshot::WorldSnapshot;
+pub use engine::mahou::Mahou;
+pub use engine::weapon::Weapon;
+/// used by save migrations
+pub(crate) use engine::{attack::AttackDamage, combo::ComboInput, damage::Element, entity::{CollideRadius, Facing, PlayerState, Position, Velocity}, health::Health, soul::SoulPower, snapshot::{ComboSnapshot, MonsterSnapshot, OwnerSnapshot, PlayerSnapshot, ProjectileSnapshot, TimerSnapshot, WeaponSnapshot}};
+pub(crate) use sprite::sprite::SpriteSize;
 pub use sprite::SpriteSystemPlugins;
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{MousePosition, WindowSize, consts::{AppState, CAMERA_SYNC_SPEED}};
+use crate::{MousePosition, WindowSize, config::GameConfig, consts::{AIM_FOLLOW_SPEED, AppState, CAMERA_SYNC_SPEED}, input::{InputActionSystem, InputActions}};
 
 use super::engine::entity::{Player, Position};
 
@@ -22,13 +22,24 @@ fn sync_camera_with_player(
 
 pub struct MouseDirection(pub Vec2);
 
+/// aim with the mouse, or the right stick while playing with a gamepad.
+/// the mouse aim is smoothed by `mouse_sensitivity`.
 fn update_mouse_direction(
+  time: Res<Time>,
+  config: Res<GameConfig>,
   mouse_position: Res<MousePosition>,
   window_size: Res<WindowSize>,
+  actions: Res<InputActions>,
   mut direction: ResMut<MouseDirection>,
   camera_query: Query<&Transform, With<GameCamera>>,
   player_query: Query<&Position, With<Player>>,
 ) {
+  if actions.using_gamepad() {
+    if let Some(aim) = actions.aim() {
+      direction.0 = aim;
+    }
+    return;
+  }
   if let Ok(position) = player_query.single() {
     if let Ok(transform) = camera_query.single() {
       let camera = position.0 - Vec2::new(transform.translation.x, transform.translation.y);
@@ -36,7 +47,17 @@ fn update_mouse_direction(
       let vector = (mouse - camera).normalize_or_zero();
 
       if vector != Vec2::ZERO {
-        direction.0 = vector;
+        // full sensitivity follows the mouse right away, lower ones smooth the aim
+        let sensitivity = config.mouse_sensitivity();
+        let t = if sensitivity >= 1.0 {
+          1.0
+        } else {
+          let speed = AIM_FOLLOW_SPEED * sensitivity / (1.0 - sensitivity);
+          1.0 - (-speed * time.delta_seconds()).exp()
+        };
+        let smoothed = (direction.0 + (vector - direction.0) * t).normalize_or_zero();
+        // turning around passes through zero, jump to the mouse then
+        direction.0 = if smoothed != Vec2::ZERO { smoothed } else { vector };
       }
     }
   }
@@ -48,7 +69,7 @@ impl Plugin for CameraPlugin {
   fn build(&self, app: &mut App) {
     app
       .insert_resource(MouseDirection(Vec2::ZERO))
-      .add_system_to_stage(CoreStage::PreUpdate, update_mouse_direction)
+      .add_system_to_stage(CoreStage::PreUpdate, update_mouse_direction.after(InputActionSystem))
       .add_system_set(
         SystemSet::on_update(AppState::InGame)
           .with_system(sync_camera_with_player)
@@ -5,6 +5,8 @@ use bevy::prelude::*;
 
 use super::engine::entity::Player;
 use super::engine::health::Health;
+use super::engine::mahou::Casting;
+use super::engine::mahou::MahouBook;
 use super::engine::soul::SoulPower;
 
 struct ControlPanelUI;
@@ -41,6 +43,7 @@ impl FromWorld for ControlPanelMaterials {
 struct HealthBarUILosing;
 struct HealthBarUI;
 struct EnergyBarUI;
+struct MahouListUI;
 
 fn setup_control_panel(
   mut commands: Commands,
@@ -163,6 +166,28 @@ fn setup_control_panel(
                 })
                 .insert(EnergyBarUI);
             });
+          // learnt spells, only shown while casting
+          parent
+            .spawn_bundle(TextBundle {
+              style: Style {
+                margin: Rect {
+                  top: Val::Px(10.0),
+                  ..Default::default()
+                },
+                ..Default::default()
+              },
+              text: Text::with_section(
+                "",
+                TextStyle {
+                  font: font_assets.default_font.clone(),
+                  font_size: 24.0,
+                  color: Color::BLACK,
+                },
+                Default::default(),
+              ),
+              ..Default::default()
+            })
+            .insert(MahouListUI);
         });
     });
   commands.insert_resource(HealthLosing(0.0));
@@ -205,6 +230,37 @@ fn update_energy_bar(mut query: Query<&mut Style, With<EnergyBarUI>>, save: Res<
   }
 }
 
+/// list the learnt spells with the chosen one in brackets
+fn update_mahou_list(
+  save: Res<GameSave>,
+  book: Res<MahouBook>,
+  player_query: Query<&Casting, With<Player>>,
+  mut query: Query<&mut Text, With<MahouListUI>>,
+) {
+  let value = match player_query.single() {
+    Ok(casting) => save
+      .unlocked_mahou
+      .iter()
+      .enumerate()
+      .filter_map(|(index, mahou)| {
+        let spell = book.get(*mahou)?;
+        Some(if index == casting.0 {
+          format!("[{}]", spell.name)
+        } else {
+          spell.name.clone()
+        })
+      })
+      .collect::<Vec<_>>()
+      .join("  "),
+    Err(_) => String::new(),
+  };
+  for mut text in query.iter_mut() {
+    if text.sections[0].value != value {
+      text.sections[0].value = value.clone();
+    }
+  }
+}
+
 fn destroy_control_panel(mut commands: Commands, query: Query<Entity, With<ControlPanelUI>>) {
   for entity in query.iter() {
     commands.entity(entity).despawn_recursive();
@@ -240,6 +296,7 @@ impl Plugin for ControlPanelPlugin {
           .with_system(update_health_bar)
           .with_system(update_energy_bar)
           .with_system(update_health_losing_bar)
+          .with_system(update_mahou_list)
           .with_system(sync_player_status)
       )
       .add_system_set(
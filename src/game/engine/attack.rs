@@ -1,10 +1,11 @@
-use std::{collections::BTreeSet, iter::FromIterator};
+use std::{collections::{BTreeSet, HashMap}, iter::FromIterator};
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{consts::AppState, game::{entity::attack::{AttackBundle, AttackSpriteType}, sprite::sprite::{SpriteRotation, SpriteSize}}};
+use crate::{consts::{AppState, HIT_GROUP_EXPIRE_SECONDS}, game::{entity::attack::{AttackBundle, AttackSpriteType}, sprite::sprite::{SpriteRotation, SpriteSize}, stages::DamageLabel}};
 
-use super::{entity::{CollideRadius, Position}, health::{Health, LockHealth}};
+use super::{damage::Element, entity::{CollideRadius, Position}};
 
 #[derive(Debug)]
 pub struct GroupAttack {
@@ -12,6 +13,68 @@ pub struct GroupAttack {
   pub entities: Vec<Entity>,
   pub damage: AttackDamage,
   pub from: Option<Entity>,
+  /// attacks sent over several frames share a group
+  pub hit_group: Option<HitGroup>,
+}
+
+/// Id of a swing, see `HitRegistry`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HitGroupId(u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct HitGroup {
+  pub id: HitGroupId,
+  /// seconds before a target can be hit again, `None` for once per swing
+  pub rehit_interval: Option<f32>,
+}
+
+struct HitGroupRecord {
+  /// when the last attack of the group was sent
+  last_active: f64,
+  /// when each target was hit
+  hits: HashMap<Entity, f64>,
+}
+
+/// Targets hit by every group, so that multi-frame attacks
+/// do not hit once per frame
+#[derive(Default)]
+pub struct HitRegistry {
+  next_id: u32,
+  groups: HashMap<HitGroupId, HitGroupRecord>,
+}
+
+impl HitRegistry {
+  pub fn new_group(&mut self) -> HitGroupId {
+    self.next_id = self.next_id.wrapping_add(1);
+    HitGroupId(self.next_id)
+  }
+
+  /// whether the group is still sending attacks
+  pub fn is_active(&self, id: HitGroupId) -> bool {
+    self.groups.contains_key(&id)
+  }
+
+  fn touch(&mut self, id: HitGroupId, now: f64) -> &mut HitGroupRecord {
+    let record = self.groups.entry(id).or_insert_with(|| HitGroupRecord {
+      last_active: now,
+      hits: HashMap::new(),
+    });
+    record.last_active = now;
+    record
+  }
+
+  /// record a hit, `false` if the target can not be hit by the group yet
+  fn try_hit(&mut self, group: &HitGroup, target: Entity, now: f64) -> bool {
+    let record = self.touch(group.id, now);
+    if let Some(&last_hit) = record.hits.get(&target) {
+      match group.rehit_interval {
+        Some(interval) if now - last_hit >= interval as f64 => {}
+        _ => return false,
+      }
+    }
+    record.hits.insert(target, now);
+    true
+  }
 }
 
 #[derive(Debug)]
@@ -79,7 +142,18 @@ pub enum AttackArea {
   }
 }
 
-#[derive(Clone, Copy, Debug)]
+impl AttackArea {
+  /// center of the area
+  pub fn origin(&self) -> Vec2 {
+    match *self {
+      AttackArea::Circle { o, .. } => o,
+      AttackArea::HalfCircle { o, .. } => o,
+      AttackArea::Rectangle { o, w, v, .. } => o + v * w / 2.0,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum AttackDamage {
   Physical {
     damage: u32,
@@ -88,17 +162,29 @@ pub enum AttackDamage {
 
   Magical {
     damage: u32,
-  }
+  },
+
+  /// magical damage of an element
+  Elemental {
+    damage: u32,
+    element: Element,
+  },
 }
 
 /// convert group attack to single attack
 fn flat_group_damage(
   mut commands: Commands,
+  time: Res<Time>,
+  mut registry: ResMut<HitRegistry>,
   mut group_attacks: EventReader<GroupAttack>,
   mut single_attacks: EventWriter<SingleAttack>,
   query: Query<(Entity, &Position, &CollideRadius)>,
 ) {
   for attack in group_attacks.iter() {
+    // a swing into empty air is still going on
+    if let Some(group) = &attack.hit_group {
+      registry.touch(group.id, time.seconds_since_startup());
+    }
     let mut set: BTreeSet<Entity> = BTreeSet::from_iter(attack.entities.clone().into_iter());
 
     // insert effects
@@ -166,6 +252,11 @@ fn flat_group_damage(
     }
 
     for entity in set {
+      if let Some(group) = &attack.hit_group {
+        if !registry.try_hit(group, entity, time.seconds_since_startup()) {
+          continue;
+        }
+      }
       single_attacks.send(SingleAttack {
         entity,
         damage: attack.damage,
@@ -175,31 +266,6 @@ fn flat_group_damage(
   }
 }
 
-/// perform damage to targeted entity
-fn recieve_damage(
-  mut attacks: EventReader<SingleAttack>,
-  mut query: Query<(&mut Health, Option<&LockHealth>)>,
-) {
-  for attack in attacks.iter() {
-    if let Ok((mut health, lock_health)) = query.get_mut(attack.entity) {
-
-      // FIXME: very original
-      let damage = match attack.damage {
-        AttackDamage::Physical { damage, .. } => damage,
-        AttackDamage::Magical { damage } => damage,
-      };
-
-      if lock_health.is_some() {
-        health.recieve_damage_locked(damage);
-      } else {
-        health.recieve_damage(damage);
-      }
-
-      // TODO: play repellent here
-    }
-  }
-}
-
 macro_rules! create_cool_down_system {
   ($func_name:ident, $t:ty) => {
     fn $func_name(
@@ -216,6 +282,14 @@ macro_rules! create_cool_down_system {
   };
 }
 
+/// forget groups which stopped sending attacks
+fn expire_hit_groups(time: Res<Time>, mut registry: ResMut<HitRegistry>) {
+  let now = time.seconds_since_startup();
+  registry
+    .groups
+    .retain(|_, record| now - record.last_active < HIT_GROUP_EXPIRE_SECONDS as f64);
+}
+
 /// Remove entity itself
 pub struct RemovalCoolDown(pub Timer);
 
@@ -238,10 +312,11 @@ impl Plugin for AttackPlugin {
     app
       .add_event::<GroupAttack>()
       .add_event::<SingleAttack>()
+      .init_resource::<HitRegistry>()
       .add_system_set(
         SystemSet::on_update(AppState::InGame)
-          .with_system(flat_group_damage)
-          .with_system(recieve_damage)
+          .with_system(flat_group_damage.label("flat").before(DamageLabel::Compute))
+          .with_system(expire_hit_groups.after("flat"))
           .with_system(update_removal_cool_down)
       );
   }
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{game::{entity::projectile::ProjectileBundle, sfx::{CombatSound, CombatSoundEvent}, sprite::sprite::SpriteSize}, input::InputAction};
+
+use super::{attack::{AttackArea, AttackDamage, GroupAttack}, entity::{CollideRadius, Position, Velocity}, projectile::BulletProps};
+
+/// Inputs of combos
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComboInput {
+  Attack1,
+  Attack2,
+}
+
+impl ComboInput {
+  /// checked in order, the first pressed one wins
+  pub const ALL: [ComboInput; 2] = [ComboInput::Attack1, ComboInput::Attack2];
+
+  pub fn action(&self) -> InputAction {
+    match self {
+      ComboInput::Attack1 => InputAction::Attack1,
+      ComboInput::Attack2 => InputAction::Attack2,
+    }
+  }
+}
+
+/// Area of a combo attack, placed at the attacker and turned to the aim
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum ComboArea {
+  Circle { r: f32 },
+  HalfCircle { r: f32 },
+  Rectangle { w: f32, h: f32 },
+}
+
+impl ComboArea {
+  pub fn place(&self, o: Vec2, v: Vec2) -> AttackArea {
+    match *self {
+      ComboArea::Circle { r } => AttackArea::Circle { o, r },
+      ComboArea::HalfCircle { r } => AttackArea::HalfCircle { o, r, v },
+      ComboArea::Rectangle { w, h } => AttackArea::Rectangle { o, w, h, v },
+    }
+  }
+}
+
+/// What a combo node emits once its animation ends
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum ComboEmit {
+  Attack {
+    area: ComboArea,
+    damage: AttackDamage,
+  },
+  Projectile {
+    speed: f32,
+    radius: f32,
+    /// sprite size
+    size: f32,
+    damage: AttackDamage,
+  },
+}
+
+impl ComboEmit {
+  /// send the attack or shoot the projectile of `owner` towards `direction`
+  pub fn emit(
+    &self,
+    commands: &mut Commands,
+    owner: Entity,
+    position: Vec2,
+    direction: Vec2,
+    attacks: &mut EventWriter<GroupAttack>,
+    sounds: &mut EventWriter<CombatSoundEvent>,
+  ) {
+    match *self {
+      ComboEmit::Attack { area, damage } => {
+        attacks.send(GroupAttack {
+          area: area.place(position, direction),
+          entities: Vec::new(),
+          damage,
+          from: Some(owner),
+          hit_group: None,
+        });
+      }
+      ComboEmit::Projectile { speed, radius, size, damage } => {
+        commands.spawn_bundle(ProjectileBundle {
+          position: Position(position),
+          velocity: Velocity(direction * speed),
+          bullet: BulletProps {
+            owner: Some(owner),
+            damage: Some(damage),
+          },
+          radius: CollideRadius(radius),
+          scale: SpriteSize(Vec2::new(size, size)),
+          ..Default::default()
+        });
+        sounds.send(CombatSoundEvent {
+          sound: CombatSound::Shoot,
+          position,
+        });
+      }
+    }
+  }
+}
+
+/// A step of a combo
+#[derive(Debug, Deserialize)]
+pub struct ComboNode {
+  /// name of the weapon sprite shown while attacking
+  pub sprite: String,
+  /// seconds before the attack is emitted
+  pub animation: f32,
+  /// seconds before the next attack
+  pub cool_down: f32,
+  /// seconds to continue the combo, `None` ends it
+  #[serde(default)]
+  pub window: Option<f32>,
+  #[serde(default)]
+  pub soul_cost: u32,
+  pub emit: ComboEmit,
+  #[serde(default)]
+  pub next: HashMap<ComboInput, ComboNode>,
+}
+
+/// Trie of combos, nodes are found by the path of inputs
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct ComboTree {
+  root: HashMap<ComboInput, ComboNode>,
+}
+
+impl ComboTree {
+  pub fn get(&self, path: &[ComboInput]) -> Option<&ComboNode> {
+    let mut nodes = &self.root;
+    let mut node = None;
+    for input in path {
+      let next = nodes.get(input)?;
+      nodes = &next.next;
+      node = Some(next);
+    }
+    node
+  }
+
+  /// path after pressing `input`, continuing `prefix` if possible
+  /// or starting a new combo from the root
+  pub fn walk(&self, prefix: &[ComboInput], input: ComboInput) -> Option<Vec<ComboInput>> {
+    let mut path = prefix.to_vec();
+    path.push(input);
+    if self.get(&path).is_some() {
+      Some(path)
+    } else if self.root.contains_key(&input) {
+      Some(vec![input])
+    } else {
+      None
+    }
+  }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{consts::AppState, game::{engine::entity::Controlling, stages::AttackPriority}};
+use crate::{consts::AppState, game::{engine::entity::Controlling, sfx::{CombatSound, CombatSoundEvent}, stages::AttackPriority}, input::{InputAction, InputActions}};
 
 use super::{attack::{AttackArea, AttackDamage, GroupAttack}, entity::Position, soul::SoulPower};
 
@@ -15,18 +15,28 @@ pub struct CounterAttackTarget;
 fn trigger_counter_attack(
   mut commands: Commands,
   mut attacks: EventWriter<GroupAttack>,
-  mut mouse_input: ResMut<Input<MouseButton>>,
+  mut sounds: EventWriter<CombatSoundEvent>,
+  mut actions: ResMut<InputActions>,
   mut query: Query<(Entity, &Position, &mut SoulPower), (With<Controlling>, With<CounterAttack>)>,
   obj_query: Query<Entity, With<CounterAttackTarget>>,
 ) {
-  if mouse_input.just_pressed(MouseButton::Left) {
+  if actions.just_pressed(InputAction::Attack1) {
     if let Ok((entity, position, mut soul)) = query.single_mut() {
-      mouse_input.clear_just_pressed(MouseButton::Left);
+      actions.clear_just_pressed(InputAction::Attack1);
 
       soul.obtain(50);
       commands.entity(entity)
         .remove::<CounterAttack>();
 
+      sounds.send(CombatSoundEvent {
+        sound: CombatSound::CounterAttack,
+        position: position.0,
+      });
+      sounds.send(CombatSoundEvent {
+        sound: CombatSound::SoulGain,
+        position: position.0,
+      });
+
       attacks.send(GroupAttack {
         area: AttackArea::Circle {
           o: position.0,
@@ -39,6 +49,7 @@ fn trigger_counter_attack(
           power: 2,
         },
         from: Some(entity),
+        hit_group: None,
       });
     }
   }
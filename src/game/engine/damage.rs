@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::{AppState, KNOCKBACK_SECONDS, KNOCKBACK_SPEED, STAGGER_SECONDS}, game::stages::DamageLabel};
+
+use super::{attack::{AttackDamage, SingleAttack}, entity::{Position, Velocity}, health::Health};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Element {
+  Fire,
+  Water,
+  Wind,
+  Thunder,
+}
+
+/// Flat reduction of physical damage
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Defense(pub u32);
+
+/// Ratio of magical damage reduced, negative for weakness
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Resistances {
+  /// against all magical damage
+  pub magic: f32,
+  pub fire: f32,
+  pub water: f32,
+  pub wind: f32,
+  pub thunder: f32,
+}
+
+impl Resistances {
+  pub fn get(&self, element: Element) -> f32 {
+    match element {
+      Element::Fire => self.fire,
+      Element::Water => self.water,
+      Element::Wind => self.wind,
+      Element::Thunder => self.thunder,
+    }
+  }
+}
+
+/// Physical power over the poise staggers and knocks back
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Poise(pub u32);
+
+/// Entity which can not act for a while
+pub struct Staggered(pub Timer);
+
+/// Pushed away from the attacker
+pub struct Knockback {
+  pub velocity: Vec2,
+  pub timer: Timer,
+}
+
+/// Damage about to be dealt
+#[derive(Debug)]
+pub struct Damage {
+  pub target: Entity,
+  pub from: Option<Entity>,
+  pub attack: AttackDamage,
+  /// health to lose, after defense and resistances
+  pub amount: u32,
+  /// physical power, after the poise
+  pub power: u32,
+}
+
+/// Damages of this frame, modified by `DamageLabel::PreDamage` hooks
+#[derive(Default)]
+pub struct PendingDamages(pub Vec<Damage>);
+
+/// Damage dealt to an entity, read by `DamageLabel::PostDamage` hooks
+#[derive(Debug)]
+pub struct DamageDealt {
+  pub target: Entity,
+  pub from: Option<Entity>,
+  pub amount: u32,
+  pub staggered: bool,
+}
+
+/// scale damage by a resistance ratio
+fn resist(damage: u32, resistance: f32) -> u32 {
+  (damage as f32 * (1.0 - resistance.min(1.0))).round() as u32
+}
+
+/// apply defense, resistances and poise to attacks
+fn compute_damage(
+  mut attacks: EventReader<SingleAttack>,
+  mut damages: ResMut<PendingDamages>,
+  query: Query<(Option<&Defense>, Option<&Resistances>, Option<&Poise>), With<Health>>,
+) {
+  for attack in attacks.iter() {
+    let (defense, resistances, poise) = match query.get(attack.entity) {
+      Ok(components) => components,
+      Err(_) => continue,
+    };
+    let defense = defense.copied().unwrap_or_default();
+    let resistances = resistances.copied().unwrap_or_default();
+    let poise = poise.copied().unwrap_or_default();
+
+    let (amount, power) = match attack.damage {
+      AttackDamage::Physical { damage, power } => (
+        // hits always hurt a little
+        damage.saturating_sub(defense.0).max(damage.min(1)),
+        power.saturating_sub(poise.0),
+      ),
+      AttackDamage::Magical { damage } => (resist(damage, resistances.magic), 0),
+      AttackDamage::Elemental { damage, element } => (
+        resist(resist(damage, resistances.magic), resistances.get(element)),
+        0,
+      ),
+    };
+
+    damages.0.push(Damage {
+      target: attack.entity,
+      from: attack.from,
+      attack: attack.damage,
+      amount,
+      power,
+    });
+  }
+}
+
+/// deal pending damages
+fn apply_damage(
+  mut commands: Commands,
+  mut damages: ResMut<PendingDamages>,
+  mut dealt: EventWriter<DamageDealt>,
+  mut query: Query<(&mut Health, Option<&Position>, Option<&mut Velocity>)>,
+  position_query: Query<&Position>,
+) {
+  for damage in damages.0.drain(..) {
+    let (mut health, position, velocity) = match query.get_mut(damage.target) {
+      Ok(components) => components,
+      Err(_) => continue,
+    };
+    health.recieve_damage(damage.amount);
+
+    let staggered = damage.power > 0;
+    if staggered {
+      if let Some(mut velocity) = velocity {
+        velocity.0 = Vec2::ZERO;
+      }
+      let mut target = commands.entity(damage.target);
+      target.insert(Staggered(Timer::from_seconds(STAGGER_SECONDS, false)));
+
+      // push away from the attacker
+      let from = damage.from.and_then(|from| position_query.get(from).ok());
+      if let (Some(position), Some(from)) = (position, from) {
+        let direction = (position.0 - from.0).normalize_or_zero();
+        if direction != Vec2::ZERO {
+          target.insert(Knockback {
+            velocity: direction * KNOCKBACK_SPEED * damage.power as f32,
+            timer: Timer::from_seconds(KNOCKBACK_SECONDS, false),
+          });
+        }
+      }
+    }
+
+    dealt.send(DamageDealt {
+      target: damage.target,
+      from: damage.from,
+      amount: damage.amount,
+      staggered,
+    });
+  }
+}
+
+/// move knocked back entities, slowing down to the end
+fn update_knockback(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut query: Query<(Entity, &mut Knockback, &mut Position)>,
+) {
+  for (entity, mut knockback, mut position) in query.iter_mut() {
+    let finished = knockback.timer.tick(time.delta()).finished();
+    position.0 += knockback.velocity * knockback.timer.percent_left() * time.delta_seconds();
+    if finished {
+      commands.entity(entity).remove::<Knockback>();
+    }
+  }
+}
+
+create_cool_down_system!(update_staggered, Staggered);
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<PendingDamages>()
+      .add_event::<DamageDealt>()
+      .add_system_set(
+        SystemSet::on_update(AppState::InGame)
+          .with_system(compute_damage.label(DamageLabel::Compute))
+          .with_system(
+            apply_damage
+              .label(DamageLabel::Apply)
+              .after(DamageLabel::Compute)
+              .after(DamageLabel::PreDamage),
+          )
+          .with_system(update_knockback)
+          .with_system(update_staggered)
+      );
+  }
+}
@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::combo::ComboInput;
 
 /// Indentify player
 pub struct Player;
@@ -12,28 +15,33 @@ pub struct NPC;
 /// entity which is under control
 pub struct Controlling;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Position(pub Vec2);
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Velocity(pub Vec2);
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct CollideRadius(pub f32);
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// normalized direction the entity faces, follows the last movement
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Facing(pub Vec2);
+
+/// What the player is doing, with whichever weapon
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum PlayerState {
-  ShieldAttackA,
-  ShieldAttackAA,
-  ShieldAttackAB,
-  ShieldAttackB,
-  ShieldAttackBB,
-  ShieldAttackBBB,
+  /// performing the combo node in `AttackAnimation`
+  Attack,
 
-  ShieldAssault,
-  ShieldAssaultA,
-  ShieldAssaultB,
+  Assault,
+  /// attacking with the input during an assault
+  AssaultAttack(ComboInput),
 
   Stand,
+
+  /// choosing a spell in `Casting`
+  Casting,
 }
 
 impl Default for PlayerState {
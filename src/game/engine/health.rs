@@ -1,9 +1,15 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::consts::AppState;
+use std::collections::HashMap;
+
+use crate::{consts::AppState, game::{sfx::{CombatSound, CombatSoundEvent}, stages::DamageLabel}};
+
+use super::{damage::PendingDamages, entity::Position};
 
 /// Health of entity.
 /// When it = 0, then we will remove the entity.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Health {
   pub now: u32,
   pub max: u32,
@@ -15,10 +21,6 @@ impl Health {
     self.now -= damage.min(self.now);
   }
 
-  pub fn recieve_damage_locked(&mut self, damage: u32) {
-    self.now -= damage.min(self.now - 1);
-  }
-  
   /// Recieve Heal
   pub fn recieve_heal(&mut self, heal: u32) {
     self.now += heal.min(self.max - self.now);
@@ -29,13 +31,36 @@ impl Health {
 /// which means we will lock its health no less than 1
 pub struct LockHealth;
 
+/// pre-damage hook of `LockHealth`
+fn lock_health(
+  mut damages: ResMut<PendingDamages>,
+  query: Query<&Health, With<LockHealth>>,
+) {
+  // several damages may hit in the same frame
+  let mut remaining = HashMap::new();
+  for damage in damages.0.iter_mut() {
+    if let Ok(health) = query.get(damage.target) {
+      let left = remaining.entry(damage.target).or_insert(health.now.saturating_sub(1));
+      damage.amount = damage.amount.min(*left);
+      *left -= damage.amount;
+    }
+  }
+}
+
 /// Remove entity which health is zero
 fn remove_zero_health_entity(
   mut commands: Commands,
-  query: Query<(Entity, &Health)>,
+  mut sounds: EventWriter<CombatSoundEvent>,
+  query: Query<(Entity, &Health, Option<&Position>)>,
 ) {
-  for (entity, health) in query.iter() {
+  for (entity, health, position) in query.iter() {
     if health.now == 0 {
+      if let Some(position) = position {
+        sounds.send(CombatSoundEvent {
+          sound: CombatSound::Defeat,
+          position: position.0,
+        });
+      }
       commands.entity(entity).despawn_recursive();
     }
   }
@@ -48,7 +73,8 @@ impl Plugin for HealthPlugin {
     app
       .add_system_set(
         SystemSet::on_update(AppState::InGame)
-          .with_system(remove_zero_health_entity)
+          .with_system(remove_zero_health_entity.after(DamageLabel::Apply))
+          .with_system(lock_health.label(DamageLabel::PreDamage).after(DamageLabel::Compute))
       );
   }
 }
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::read;
+
+use crate::{FontAssets, assets::asset_path, config::GameConfig, consts::AppState, game::{MouseDirection, game_saves::{SaveNotice, show_notice}, sfx::CombatSoundEvent, stages::{AttackPriority, DamageLabel}}, input::{InputAction, InputActions}, saves::GameSave};
+
+use super::{attack::GroupAttack, combo::ComboEmit, damage::Staggered, entity::{Controlling, Facing, Monster, PlayerState, Position}, health::Health, soul::SoulPower, weapon::aim_direction};
+
+/// Spells, the learnt ones are kept in `GameSave`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Mahou {
+  Tama,
+  Kaen,
+  Ikazuchi,
+}
+
+impl Mahou {
+  /// checked in order when learning spells
+  pub const ALL: [Mahou; 3] = [Mahou::Tama, Mahou::Kaen, Mahou::Ikazuchi];
+}
+
+/// A spell, read from `assets/mahou.ron`
+#[derive(Debug, Deserialize)]
+pub struct MahouDef {
+  /// shown in the casting list
+  pub name: String,
+  /// monsters to defeat before learning it, 0 for spells known from the start
+  #[serde(default)]
+  pub unlock: u32,
+  pub soul_cost: u32,
+  /// seconds before the next spell
+  pub cool_down: f32,
+  pub emit: ComboEmit,
+}
+
+/// All spells, spells missing in the file can not be learnt
+pub struct MahouBook(HashMap<Mahou, MahouDef>);
+
+impl MahouBook {
+  /// relative to `assets`
+  const MAHOU_FILE: &'static str = "mahou.ron";
+
+  fn load() -> MahouBook {
+    let path = asset_path(MahouBook::MAHOU_FILE);
+    match read(&path) {
+      Ok(data) => match ron::de::from_bytes::<HashMap<Mahou, MahouDef>>(&data[..]) {
+        Ok(spells) => return MahouBook(spells),
+        Err(err) => warn!("failed to parse {}: {}", path.display(), err),
+      },
+      Err(err) => warn!("failed to read {}: {}", path.display(), err),
+    }
+    MahouBook(HashMap::new())
+  }
+
+  pub fn get(&self, mahou: Mahou) -> Option<&MahouDef> {
+    self.0.get(&mahou)
+  }
+}
+
+/// In the spell-casting mode, with the index of the chosen spell in `GameSave::unlocked_mahou`
+pub(crate) struct Casting(pub(crate) usize);
+
+struct MahouCoolDown(Timer);
+
+/// open the spell-casting mode, or close it when pressed again
+fn toggle_casting(
+  mut commands: Commands,
+  save: Res<GameSave>,
+  actions: Res<InputActions>,
+  mut query: Query<(Entity, &mut PlayerState), (With<Controlling>, Without<MahouCoolDown>, Without<Staggered>)>,
+) {
+  if !actions.just_pressed(InputAction::StartMahou) {
+    return;
+  }
+  if let Ok((entity, mut state)) = query.single_mut() {
+    if *state == PlayerState::Casting {
+      *state = PlayerState::Stand;
+      commands.entity(entity).remove::<Casting>();
+    } else if *state == PlayerState::Stand && !save.unlocked_mahou.is_empty() {
+      *state = PlayerState::Casting;
+      commands.entity(entity).insert(Casting(0));
+    }
+  }
+}
+
+/// `Attack2` chooses the next spell, `Attack1` casts the chosen one
+fn cast_mahou(
+  mut commands: Commands,
+  save: Res<GameSave>,
+  book: Res<MahouBook>,
+  config: Res<GameConfig>,
+  actions: Res<InputActions>,
+  mouse_direction: Res<MouseDirection>,
+  mut attacks: EventWriter<GroupAttack>,
+  mut sounds: EventWriter<CombatSoundEvent>,
+  mut query: Query<(Entity, &Position, Option<&Facing>, &mut Casting, &mut PlayerState, &mut SoulPower), With<Controlling>>,
+) {
+  if let Ok((entity, position, facing, mut casting, mut state, mut soul)) = query.single_mut() {
+    if actions.just_pressed(InputAction::Attack2) {
+      casting.0 = (casting.0 + 1) % save.unlocked_mahou.len().max(1);
+    }
+    if !actions.just_pressed(InputAction::Attack1) {
+      return;
+    }
+    let spell = match save.unlocked_mahou.get(casting.0).and_then(|mahou| book.get(*mahou)) {
+      Some(spell) => spell,
+      None => return,
+    };
+    if !soul.cost(spell.soul_cost) {
+      return;
+    }
+
+    let direction = aim_direction(config.attack_to_mouse(), &mouse_direction, facing);
+    spell.emit.emit(&mut commands, entity, position.0, direction, &mut attacks, &mut sounds);
+    *state = PlayerState::Stand;
+    commands.entity(entity)
+      .remove::<Casting>()
+      .insert(MahouCoolDown(Timer::from_seconds(spell.cool_down, false)));
+  }
+}
+
+/// staggered players drop the spell
+fn interrupt_casting(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut PlayerState), (With<Casting>, With<Staggered>)>,
+) {
+  for (entity, mut state) in query.iter_mut() {
+    *state = PlayerState::Stand;
+    commands.entity(entity).remove::<Casting>();
+  }
+}
+
+/// progression counts monsters defeated by any means
+fn count_defeated_monsters(
+  mut save: ResMut<GameSave>,
+  query: Query<&Health, (With<Monster>, Changed<Health>)>,
+) {
+  let defeated = query.iter().filter(|health| health.now == 0).count() as u32;
+  if defeated > 0 {
+    save.defeated_monsters += defeated;
+  }
+}
+
+/// learn every spell the progression has reached
+fn unlock_mahou(
+  mut commands: Commands,
+  mut save: ResMut<GameSave>,
+  book: Res<MahouBook>,
+  font_assets: Res<FontAssets>,
+  notice_query: Query<Entity, With<SaveNotice>>,
+) {
+  for mahou in Mahou::ALL.iter() {
+    let spell = match book.get(*mahou) {
+      Some(spell) => spell,
+      None => continue,
+    };
+    if spell.unlock <= save.defeated_monsters && !save.unlocked_mahou.contains(mahou) {
+      save.unlocked_mahou.push(*mahou);
+      show_notice(&mut commands, &font_assets, &notice_query, &format!("魔法「{}」を覚えた", spell.name));
+    }
+  }
+}
+
+create_cool_down_system!(update_mahou_cool_down, MahouCoolDown);
+
+/// Spell-casting mode and learning spells
+pub struct MahouPlugin;
+
+impl Plugin for MahouPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(MahouBook::load())
+      .add_system_set(
+        SystemSet::on_update(AppState::InGame)
+          .with_system(toggle_casting.before(AttackPriority::Normal))
+          // the casting click must not start a combo once back to `Stand`
+          .with_system(cast_mahou.after(AttackPriority::Normal))
+          .with_system(interrupt_casting)
+          .with_system(update_mahou_cool_down)
+          .with_system(count_defeated_monsters.after(DamageLabel::Apply))
+          .with_system(unlock_mahou)
+      );
+  }
+}
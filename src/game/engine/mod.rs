@@ -2,13 +2,17 @@ use bevy::prelude::*;
 
 #[macro_use]
 pub mod attack;
+pub mod combo;
+pub mod damage;
 pub mod health;
 pub mod counter_attack;
 pub mod entity;
-pub mod shield;
+pub mod mahou;
 pub mod soul;
 pub mod movement;
 pub mod projectile;
+pub mod snapshot;
+pub mod weapon;
 
 pub struct GameSystemPlugins;
 
@@ -16,10 +20,13 @@ impl PluginGroup for GameSystemPlugins {
   fn build(&mut self, group: &mut bevy::app::PluginGroupBuilder) {
     group
       .add(movement::MovementPlugin)
-      .add(shield::ShieldPlugin)
+      .add(weapon::WeaponPlugin)
+      .add(mahou::MahouPlugin)
       .add(projectile::ProjectilePlugin)
       .add(attack::AttackPlugin)
+      .add(damage::DamagePlugin)
       .add(counter_attack::CounterAttackPlugin)
-      .add(health::HealthPlugin);
+      .add(health::HealthPlugin)
+      .add(snapshot::SnapshotPlugin);
   }
 }
@@ -1,32 +1,46 @@
 use bevy::prelude::*;
 
-use crate::{consts::{AppState, PLAYER_MOVE_SPEED}, game::stages::PhysicsLabel};
+use crate::{consts::{AppState, PLAYER_MOVE_SPEED}, game::stages::PhysicsLabel, input::{InputAction, InputActions}};
 
-use super::entity::{Controlling, Position, Velocity};
+use super::{damage::Staggered, entity::{Controlling, Facing, Position, Velocity}};
 
 /// disable WASD moving
 pub struct DisableWASD;
 
 /// update entity's velocity which has Controlling tag
 fn update_controlling_velocity(
-  keycode_input: Res<Input<KeyCode>>,
-  mut query: Query<&mut Velocity, (With<Controlling>, Without<DisableWASD>)>
+  actions: Res<InputActions>,
+  mut query: Query<(&mut Velocity, Option<&mut Facing>), (With<Controlling>, Without<DisableWASD>, Without<Staggered>)>
 ) {
-  for mut velocity in query.iter_mut() {
+  for (mut velocity, facing) in query.iter_mut() {
     let mut direction = Vec2::ZERO;
-    if keycode_input.pressed(KeyCode::A) {
+    if actions.pressed(InputAction::MoveLeftwards) {
       direction -= Vec2::X;
     }
-    if keycode_input.pressed(KeyCode::D) {
+    if actions.pressed(InputAction::MoveRightwards) {
       direction += Vec2::X;
     }
-    if keycode_input.pressed(KeyCode::W) {
+    if actions.pressed(InputAction::MoveUpwards) {
       direction += Vec2::Y;
     }
-    if keycode_input.pressed(KeyCode::S) {
+    if actions.pressed(InputAction::MoveDownwards) {
       direction -= Vec2::Y;
     }
-    let v = direction.normalize_or_zero() * PLAYER_MOVE_SPEED;
+    // the stick tilts to move slower, keys always move at full speed
+    let direction = if direction != Vec2::ZERO {
+      direction.normalize()
+    } else {
+      actions.movement()
+    };
+    let v = direction * PLAYER_MOVE_SPEED;
+
+    // keep facing the last direction after stopping
+    if let Some(mut facing) = facing {
+      let direction = direction.normalize_or_zero();
+      if direction != Vec2::ZERO && facing.0 != direction {
+        facing.0 = direction;
+      }
+    }
     
     // in order to trigger Changed<Velocity> correctly.
     if velocity.0 != v {
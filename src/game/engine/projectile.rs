@@ -39,6 +39,7 @@ fn bullet_collision(
             entities: Vec::new(),
             damage,
             from: props.owner,
+            hit_group: None,
           });
         }
 
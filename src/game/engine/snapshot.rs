@@ -0,0 +1,303 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{consts::AppState, game::{entity::{monster::MonsterBundle, player::PlayerBundle, projectile::ProjectileBundle}, game_saves::SaveGameEvent, sprite::{health::HealthBar, sprite::SpriteSize}, stages::SaveLabel}, saves::GameSave};
+
+use super::{attack::AttackDamage, combo::ComboInput, counter_attack::CounterAttack, damage::{Defense, Resistances}, entity::{CollideRadius, Controlling, Facing, Monster, Player, PlayerState, Position, Velocity}, health::Health, movement::DisableWASD, projectile::BulletProps, soul::SoulPower, weapon::{Assault, AssaultCoolDown, AttackAnimation, AttackCoolDown, AttackPrefix, Stance, Weapon}};
+
+/// Serializable form of a `Timer`
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TimerSnapshot {
+  pub(crate) duration: f32,
+  pub(crate) elapsed: f32,
+  pub(crate) repeating: bool,
+}
+
+impl From<&Timer> for TimerSnapshot {
+  fn from(timer: &Timer) -> Self {
+    TimerSnapshot {
+      duration: timer.duration().as_secs_f32(),
+      elapsed: timer.elapsed().as_secs_f32(),
+      repeating: timer.repeating(),
+    }
+  }
+}
+
+impl From<TimerSnapshot> for Timer {
+  fn from(snapshot: TimerSnapshot) -> Self {
+    let mut timer = Timer::from_seconds(snapshot.duration, snapshot.repeating);
+    timer.set_elapsed(Duration::from_secs_f32(snapshot.elapsed));
+    timer
+  }
+}
+
+/// A combo timer with the path of its node in the combo tree of the weapon
+///
+/// Laid out like the tuple it replaced, so older saves still read with bincode
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ComboSnapshot {
+  pub(crate) timer: TimerSnapshot,
+  pub(crate) path: Vec<ComboInput>,
+}
+
+/// Weapon cool downs and combo timers of the player
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WeaponSnapshot {
+  pub(crate) prefix: Option<ComboSnapshot>,
+  pub(crate) animation: Option<ComboSnapshot>,
+  pub(crate) assault: Option<TimerSnapshot>,
+  pub(crate) attack_cool_down: Option<TimerSnapshot>,
+  pub(crate) assault_cool_down: Option<TimerSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerSnapshot {
+  pub(crate) position: Position,
+  pub(crate) velocity: Velocity,
+  /// the aim without the mouse
+  pub(crate) facing: Facing,
+  pub(crate) health: Health,
+  pub(crate) soul: SoulPower,
+  pub(crate) state: PlayerState,
+  pub(crate) weapon: WeaponSnapshot,
+  pub(crate) disable_wasd: bool,
+  pub(crate) counter_attack: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MonsterSnapshot {
+  pub(crate) position: Position,
+  pub(crate) velocity: Velocity,
+  pub(crate) health: Health,
+  pub(crate) defense: Defense,
+  pub(crate) resistances: Resistances,
+}
+
+impl MonsterSnapshot {
+  /// a monster of saves which did not keep its defense, it gets the one of a new monster
+  pub(crate) fn with_default_defense(position: Position, velocity: Velocity, health: Health) -> Self {
+    let bundle = MonsterBundle::default();
+    MonsterSnapshot {
+      position,
+      velocity,
+      health,
+      defense: bundle.defense,
+      resistances: bundle.resistances,
+    }
+  }
+}
+
+/// Entity ids change after reloading, so owners are stored by role
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OwnerSnapshot {
+  Player,
+  /// index in `WorldSnapshot::monsters`
+  Monster(usize),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectileSnapshot {
+  pub(crate) position: Position,
+  pub(crate) velocity: Velocity,
+  pub(crate) radius: CollideRadius,
+  pub(crate) size: SpriteSize,
+  pub(crate) owner: Option<OwnerSnapshot>,
+  pub(crate) damage: Option<AttackDamage>,
+}
+
+/// Everything alive in the game world
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorldSnapshot {
+  pub player: Option<PlayerSnapshot>,
+  pub monsters: Vec<MonsterSnapshot>,
+  pub projectiles: Vec<ProjectileSnapshot>,
+}
+
+impl WorldSnapshot {
+  /// spawn all entities of the snapshot, the player fights with `weapon`
+  pub fn restore(&self, commands: &mut Commands, weapon: Weapon) {
+    let mut owners = HashMap::new();
+
+    if let Some(player) = &self.player {
+      let mut entity = commands.spawn_bundle(PlayerBundle {
+        position: player.position,
+        velocity: player.velocity,
+        facing: player.facing,
+        health: player.health,
+        soulpower: player.soul,
+        player_state: player.state,
+        stance: Stance(weapon),
+        ..Default::default()
+      });
+      entity.insert(Controlling);
+
+      let weapon = &player.weapon;
+      if let Some(prefix) = &weapon.prefix {
+        entity.insert(AttackPrefix(prefix.timer.into(), prefix.path.clone()));
+      }
+      if let Some(animation) = &weapon.animation {
+        entity.insert(AttackAnimation(animation.timer.into(), animation.path.clone()));
+      }
+      if let Some(timer) = weapon.assault {
+        entity.insert(Assault(timer.into()));
+      }
+      if let Some(timer) = weapon.attack_cool_down {
+        entity.insert(AttackCoolDown(timer.into()));
+      }
+      if let Some(timer) = weapon.assault_cool_down {
+        entity.insert(AssaultCoolDown(timer.into()));
+      }
+      if player.disable_wasd {
+        entity.insert(DisableWASD);
+      }
+      if player.counter_attack {
+        entity.insert(CounterAttack);
+      }
+      owners.insert(OwnerSnapshot::Player, entity.id());
+    }
+
+    for (index, monster) in self.monsters.iter().enumerate() {
+      let entity = commands
+        .spawn_bundle(MonsterBundle {
+          position: monster.position,
+          velocity: monster.velocity,
+          health: monster.health,
+          defense: monster.defense,
+          resistances: monster.resistances,
+          ..Default::default()
+        })
+        .insert(HealthBar)
+        .id();
+      owners.insert(OwnerSnapshot::Monster(index), entity);
+    }
+
+    for projectile in self.projectiles.iter() {
+      commands.spawn_bundle(ProjectileBundle {
+        position: projectile.position,
+        velocity: projectile.velocity,
+        bullet: BulletProps {
+          owner: projectile.owner.and_then(|owner| owners.get(&owner).cloned()),
+          damage: projectile.damage,
+        },
+        radius: projectile.radius,
+        scale: projectile.size,
+        ..Default::default()
+      });
+    }
+  }
+}
+
+/// capture the live world into `GameSave` right before it gets written
+fn capture_world_snapshot(
+  mut events: EventReader<SaveGameEvent>,
+  mut save: ResMut<GameSave>,
+  player_query: Query<
+    (
+      Entity,
+      &Position,
+      &Velocity,
+      &Facing,
+      &Health,
+      &SoulPower,
+      &PlayerState,
+      &Stance,
+      Option<&DisableWASD>,
+      Option<&CounterAttack>,
+    ),
+    (With<Player>, With<Controlling>),
+  >,
+  weapon_query: Query<(
+    Option<&AttackPrefix>,
+    Option<&AttackAnimation>,
+    Option<&Assault>,
+    Option<&AttackCoolDown>,
+    Option<&AssaultCoolDown>,
+  )>,
+  monster_query: Query<(Entity, &Position, &Velocity, &Health, &Defense, &Resistances), With<Monster>>,
+  projectile_query: Query<(&Position, &Velocity, &CollideRadius, &SpriteSize, &BulletProps)>,
+) {
+  if events.iter().count() == 0 {
+    return;
+  }
+
+  let player = player_query.single().ok().map(
+    |(entity, position, velocity, facing, health, soul, state, stance, disable_wasd, counter_attack)| {
+      let weapon = match weapon_query.get(entity) {
+        Ok((prefix, animation, assault, attack_cool_down, assault_cool_down)) => WeaponSnapshot {
+          prefix: prefix.map(|prefix| ComboSnapshot { timer: (&prefix.0).into(), path: prefix.1.clone() }),
+          animation: animation.map(|animation| ComboSnapshot { timer: (&animation.0).into(), path: animation.1.clone() }),
+          assault: assault.map(|assault| (&assault.0).into()),
+          attack_cool_down: attack_cool_down.map(|cd| (&cd.0).into()),
+          assault_cool_down: assault_cool_down.map(|cd| (&cd.0).into()),
+        },
+        Err(_) => WeaponSnapshot::default(),
+      };
+      (entity, stance.0, PlayerSnapshot {
+        position: *position,
+        velocity: *velocity,
+        facing: *facing,
+        health: *health,
+        soul: *soul,
+        // the spell-casting mode is not kept
+        state: if *state == PlayerState::Casting { PlayerState::Stand } else { *state },
+        weapon,
+        disable_wasd: disable_wasd.is_some(),
+        counter_attack: counter_attack.is_some(),
+      })
+    },
+  );
+
+  let mut owners = HashMap::new();
+  if let Some((entity, weapon, _)) = &player {
+    owners.insert(*entity, OwnerSnapshot::Player);
+    save.weapon = *weapon;
+  }
+
+  let mut monsters = Vec::new();
+  for (index, (entity, position, velocity, health, defense, resistances)) in monster_query.iter().enumerate() {
+    owners.insert(entity, OwnerSnapshot::Monster(index));
+    monsters.push(MonsterSnapshot {
+      position: *position,
+      velocity: *velocity,
+      health: *health,
+      defense: *defense,
+      resistances: *resistances,
+    });
+  }
+
+  let projectiles = projectile_query
+    .iter()
+    .map(|(position, velocity, radius, size, bullet)| ProjectileSnapshot {
+      position: *position,
+      velocity: *velocity,
+      radius: *radius,
+      size: *size,
+      owner: bullet.owner.and_then(|owner| owners.get(&owner).cloned()),
+      damage: bullet.damage,
+    })
+    .collect();
+
+  save.world = Some(WorldSnapshot {
+    player: player.map(|(_, _, player)| player),
+    monsters,
+    projectiles,
+  });
+}
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_system_set(
+        SystemSet::on_update(AppState::InGame)
+          .with_system(capture_world_snapshot.label(SaveLabel::CaptureWorld))
+      )
+      .add_system_set(
+        SystemSet::on_exit(AppState::InGame)
+          .with_system(capture_world_snapshot.label(SaveLabel::CaptureWorld))
+      );
+  }
+}
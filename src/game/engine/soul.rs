@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
 
 /// soul power of an entity
 /// (now, max)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SoulPower {
   pub now: u32,
   pub max: u32,
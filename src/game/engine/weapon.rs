@@ -0,0 +1,364 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::read;
+
+use crate::{assets::asset_path, config::GameConfig, consts::AppState, game::{MouseDirection, sfx::CombatSoundEvent, stages::{AttackPriority, PhysicsLabel}}, input::{InputAction, InputActions}};
+
+use super::{attack::{AttackDamage, GroupAttack, HitGroup, HitGroupId, HitRegistry}, combo::{ComboArea, ComboInput, ComboTree}, damage::Staggered, entity::{Controlling, Facing, PlayerState, Position, Velocity}, movement::DisableWASD, soul::SoulPower};
+
+/// Weapons the player fights with, every weapon is a stance
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Weapon {
+  Shield,
+  DualBlade,
+}
+
+impl Weapon {
+  /// switched through in order
+  pub const ALL: [Weapon; 2] = [Weapon::Shield, Weapon::DualBlade];
+
+  /// relative to `assets`, like the sprites listed in it
+  fn file(&self) -> &'static str {
+    match self {
+      Weapon::Shield => "weapons/shield.ron",
+      Weapon::DualBlade => "weapons/dual_blade.ron",
+    }
+  }
+
+  fn next(&self) -> Weapon {
+    let index = Weapon::ALL.iter().position(|weapon| weapon == self).unwrap_or(0);
+    Weapon::ALL[(index + 1) % Weapon::ALL.len()]
+  }
+}
+
+impl Default for Weapon {
+  fn default() -> Self {
+    Self::Shield
+  }
+}
+
+/// A sprite sheet of a single row
+#[derive(Debug, Deserialize)]
+pub struct WeaponSprite {
+  /// relative to `assets`
+  pub path: String,
+  pub columns: usize,
+}
+
+/// Attack sent every frame of an assault once triggered
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct AssaultAttack {
+  pub area: ComboArea,
+  pub damage: AttackDamage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeaponAssault {
+  pub speed: f32,
+  /// seconds of the assault
+  pub duration: f32,
+  /// seconds before the next assault
+  pub cool_down: f32,
+  #[serde(default)]
+  pub attacks: HashMap<ComboInput, AssaultAttack>,
+}
+
+/// Combos, assault and sprites of a weapon, read from its asset file
+#[derive(Debug, Deserialize)]
+pub struct WeaponDef {
+  /// size of a sprite cell
+  pub cell: (f32, f32),
+  pub sprites: HashMap<String, WeaponSprite>,
+  pub assault: WeaponAssault,
+  pub combos: ComboTree,
+}
+
+/// All weapons, the ones failed to load can not be used
+pub struct Weapons(HashMap<Weapon, WeaponDef>);
+
+impl Weapons {
+  fn load() -> Weapons {
+    let mut weapons = HashMap::new();
+    for weapon in Weapon::ALL.iter() {
+      let path = asset_path(weapon.file());
+      match read(&path) {
+        Ok(data) => match ron::de::from_bytes::<WeaponDef>(&data[..]) {
+          Ok(def) => {
+            weapons.insert(*weapon, def);
+          }
+          Err(err) => warn!("failed to parse weapon {}: {}", path.display(), err),
+        },
+        Err(err) => warn!("failed to read weapon {}: {}", path.display(), err),
+      }
+    }
+    Weapons(weapons)
+  }
+
+  pub fn get(&self, weapon: Weapon) -> Option<&WeaponDef> {
+    self.0.get(&weapon)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&Weapon, &WeaponDef)> {
+    self.0.iter()
+  }
+}
+
+/// Weapon the player is fighting with
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stance(pub Weapon);
+
+/// Used to refer next attack, with the path of the combo so far
+pub(super) struct AttackPrefix(pub(super) Timer, pub(super) Vec<ComboInput>);
+
+/// Used to trigger attack, with the path of the combo node
+pub(crate) struct AttackAnimation(pub(crate) Timer, pub(crate) Vec<ComboInput>);
+
+/// Assault time
+pub(super) struct Assault(pub(super) Timer);
+
+/// Hit group of the assault attack, which is sent every frame
+struct AssaultHitGroup(HitGroupId);
+
+
+pub(super) struct AttackCoolDown(pub(super) Timer);
+pub(super) struct AssaultCoolDown(pub(super) Timer);
+
+/// switch to the next usable weapon, only while standing
+fn switch_stance(
+  mut commands: Commands,
+  actions: Res<InputActions>,
+  weapons: Res<Weapons>,
+  mut query: Query<(Entity, &PlayerState, &mut Stance), With<Controlling>>,
+) {
+  if !actions.just_pressed(InputAction::SwitchWeapon) {
+    return;
+  }
+  if let Ok((entity, state, mut stance)) = query.single_mut() {
+    if *state != PlayerState::Stand {
+      return;
+    }
+    let mut next = stance.0.next();
+    while next != stance.0 && weapons.get(next).is_none() {
+      next = next.next();
+    }
+    if next != stance.0 {
+      stance.0 = next;
+      // combos never continue across weapons
+      commands.entity(entity).remove::<AttackPrefix>();
+    }
+  }
+}
+
+/// Trigger all kinds of common attacks by walking the combo tree of the weapon
+fn trigger_common_attack(
+  mut commands: Commands,
+  weapons: Res<Weapons>,
+  mut query: Query<
+    (Entity, &Stance, &mut PlayerState, Option<&AttackPrefix>, &mut SoulPower),
+    (With<Controlling>, Without<AttackCoolDown>, Without<Staggered>)
+  >,
+  actions: Res<InputActions>,
+) {
+  if let Ok((entity, stance, mut state, prev, mut soul)) = query.single_mut() {
+    if *state != PlayerState::Stand {
+      return;
+    }
+    let combos = match weapons.get(stance.0) {
+      Some(weapon) => &weapon.combos,
+      None => return,
+    };
+
+    let input = match ComboInput::ALL.iter().find(|input| actions.just_pressed(input.action())) {
+      Some(input) => *input,
+      None => return,
+    };
+    let prefix = prev.map(|prefix| prefix.1.as_slice()).unwrap_or(&[]);
+    let path = match combos.walk(prefix, input) {
+      Some(path) => path,
+      None => return,
+    };
+    let node = combos.get(&path).unwrap();
+    if !soul.cost(node.soul_cost) {
+      return;
+    }
+
+    *state = PlayerState::Attack;
+    let mut entity = commands.entity(entity);
+    entity
+      .insert(AttackCoolDown(Timer::from_seconds(node.cool_down, false)))
+      .insert(AttackAnimation(Timer::from_seconds(node.animation, false), path.clone()));
+    match node.window {
+      Some(window) => entity.insert(AttackPrefix(Timer::from_seconds(window, false), path)),
+      None => entity.remove::<AttackPrefix>(),
+    };
+  }
+}
+
+/// direction to the mouse, or the facing direction without mouse aiming
+pub(super) fn aim_direction(to_mouse: bool, mouse_direction: &MouseDirection, facing: Option<&Facing>) -> Vec2 {
+  match facing {
+    Some(facing) if !to_mouse => facing.0,
+    _ => mouse_direction.0,
+  }
+}
+
+fn perform_common_attack(
+  mut commands: Commands,
+  time: Res<Time>,
+  config: Res<GameConfig>,
+  weapons: Res<Weapons>,
+  mouse_direction: Res<MouseDirection>,
+  mut attack: EventWriter<GroupAttack>,
+  mut sounds: EventWriter<CombatSoundEvent>,
+  mut query: Query<(Entity, &Position, Option<&Facing>, &Stance, &mut AttackAnimation, &mut PlayerState), With<Controlling>>,
+) {
+  if let Ok((entity, position, facing, stance, mut animation, mut state)) = query.single_mut() {
+    if animation.0.tick(time.delta()).finished() {
+      let direction = aim_direction(config.attack_to_mouse(), &mouse_direction, facing);
+      commands.entity(entity).remove::<AttackAnimation>();
+
+      let node = weapons
+        .get(stance.0)
+        .and_then(|weapon| weapon.combos.get(&animation.1));
+      match node {
+        Some(node) => node.emit.emit(&mut commands, entity, position.0, direction, &mut attack, &mut sounds),
+        None => { warn!("unknown combo {:?} of {:?}", animation.1, stance.0) }
+      }
+      *state = PlayerState::Stand;
+    }
+  }
+}
+
+fn trigger_assault(
+  mut commands: Commands,
+  config: Res<GameConfig>,
+  weapons: Res<Weapons>,
+  actions: Res<InputActions>,
+  mouse_direction: Res<MouseDirection>,
+  mut query: Query<(Entity, &Stance, Option<&Facing>, &mut Velocity, &mut PlayerState), (With<Controlling>, Without<AssaultCoolDown>, Without<Staggered>)>
+) {
+  for (entity, stance, facing, mut velocity, mut state) in query.single_mut() {
+    if actions.just_pressed(InputAction::Assault) {
+      let assault = match weapons.get(stance.0) {
+        Some(weapon) => &weapon.assault,
+        None => continue,
+      };
+      let is_stand = *state == PlayerState::Stand;
+      let is_attack = *state == PlayerState::Attack;
+
+      if is_attack {
+        commands.entity(entity)
+          .remove::<AttackAnimation>();
+      }
+
+      if is_stand || is_attack {
+        *state = PlayerState::Assault;
+        velocity.0 = aim_direction(config.assault_to_mouse(), &mouse_direction, facing) * assault.speed;
+        commands.entity(entity)
+          .insert(AssaultCoolDown(Timer::from_seconds(assault.cool_down, false)))
+          .insert(Assault(Timer::from_seconds(assault.duration, false)))
+          .insert(DisableWASD);
+      }
+    }
+  }
+}
+
+fn trigger_assault_attack(
+  weapons: Res<Weapons>,
+  actions: Res<InputActions>,
+  mut query: Query<(&Stance, &mut PlayerState), With<Controlling>>,
+) {
+  for (stance, mut state) in query.single_mut() {
+    if *state == PlayerState::Assault {
+      let attacks = match weapons.get(stance.0) {
+        Some(weapon) => &weapon.assault.attacks,
+        None => continue,
+      };
+      let input = ComboInput::ALL
+        .iter()
+        .find(|input| attacks.contains_key(input) && actions.just_pressed(input.action()));
+      if let Some(input) = input {
+        *state = PlayerState::AssaultAttack(*input);
+      }
+    }
+  }
+}
+
+fn perform_assault_attack(
+  mut commands: Commands,
+  time: Res<Time>,
+  config: Res<GameConfig>,
+  weapons: Res<Weapons>,
+  mouse_direction: Res<MouseDirection>,
+  mut registry: ResMut<HitRegistry>,
+  mut group_attacks: EventWriter<GroupAttack>,
+  mut query: Query<(Entity, &Position, Option<&Facing>, &Stance, Option<&AssaultHitGroup>, &mut PlayerState, &mut Assault), With<Controlling>>,
+) {
+  for (entity, position, facing, stance, hit_group, mut state, mut assault) in query.single_mut() {
+    if assault.0.tick(time.delta()).finished() {
+      *state = PlayerState::Stand;
+      commands.entity(entity)
+        .remove::<DisableWASD>()
+        .remove::<Assault>()
+        .remove::<AssaultHitGroup>();
+      return;
+    }
+    let input = match *state {
+      PlayerState::AssaultAttack(input) => input,
+      _ => continue,
+    };
+    let attack = match weapons.get(stance.0).and_then(|weapon| weapon.assault.attacks.get(&input)) {
+      Some(attack) => *attack,
+      None => continue,
+    };
+    let direction = aim_direction(config.assault_to_mouse(), &mouse_direction, facing);
+
+    // every enermy only recieve one attack in an assault
+    let id = match hit_group {
+      Some(hit_group) => hit_group.0,
+      None => {
+        let id = registry.new_group();
+        commands.entity(entity).insert(AssaultHitGroup(id));
+        id
+      }
+    };
+
+    group_attacks.send(GroupAttack {
+      area: attack.area.place(position.0, direction),
+      entities: Vec::new(),
+      damage: attack.damage,
+      from: Some(entity),
+      hit_group: Some(HitGroup {
+        id,
+        rehit_interval: None,
+      }),
+    });
+  }
+}
+
+create_cool_down_system!(update_attack_prefix, AttackPrefix);
+create_cool_down_system!(update_attack_cool_down, AttackCoolDown);
+create_cool_down_system!(update_assault_cool_down, AssaultCoolDown);
+
+/// All controls of weapon stances
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(Weapons::load())
+      .add_system_set(
+        SystemSet::on_update(AppState::InGame)
+          .with_system(switch_stance.before(AttackPriority::Normal))
+          .with_system(trigger_common_attack.label(AttackPriority::Normal))
+          .with_system(perform_common_attack)
+          .with_system(update_attack_prefix)
+          .with_system(update_attack_cool_down)
+          .with_system(update_assault_cool_down)
+          .with_system(trigger_assault.after(PhysicsLabel::UpdateVelocity))
+          .with_system(trigger_assault_attack)
+          .with_system(perform_assault_attack)
+      );
+  }
+}
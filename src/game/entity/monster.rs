@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::game::{engine::{entity::{CollideRadius, Monster, Position, Velocity}, health::Health}, sprite::sprite::{SpriteAnimateTimer, SpriteRotation, SpriteSize}};
+use crate::game::{engine::{damage::{Defense, Poise, Resistances}, entity::{CollideRadius, Monster, Position, Velocity}, health::Health}, sprite::sprite::{SpriteAnimateTimer, SpriteRotation, SpriteSize}};
 
 #[derive(Bundle)]
 pub struct MonsterBundle {
@@ -8,6 +8,9 @@ pub struct MonsterBundle {
   pub position: Position,
   pub health: Health,
   pub collision_radius: CollideRadius,
+  pub defense: Defense,
+  pub resistances: Resistances,
+  pub poise: Poise,
 
   /// flags
   pub monster: Monster,
@@ -27,6 +30,16 @@ impl Default for MonsterBundle {
       position: Position(Vec2::ZERO),
       health: Health { now: 500, max: 500 },
       collision_radius: CollideRadius(50.0),
+      defense: Defense(4),
+      // soaked monsters, weak to fire
+      resistances: Resistances {
+        magic: 0.1,
+        fire: -0.25,
+        water: 0.5,
+        wind: 0.0,
+        thunder: 0.0,
+      },
+      poise: Poise(1),
 
       monster: Monster,
 
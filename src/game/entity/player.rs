@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::game::{engine::{entity::{CollideRadius, Player, PlayerState, Position, Velocity}, health::Health, soul::SoulPower}, sprite::sprite::{SpriteAnimateTimer, SpriteRotation, SpriteSize}};
+use crate::game::{engine::{damage::{Defense, Poise, Resistances}, entity::{CollideRadius, Facing, Player, PlayerState, Position, Velocity}, health::Health, soul::SoulPower, weapon::Stance}, sprite::sprite::{SpriteAnimateTimer, SpriteRotation, SpriteSize}};
 
 #[derive(Bundle)]
 pub struct PlayerBundle {
@@ -9,6 +9,11 @@ pub struct PlayerBundle {
   pub health: Health,
   pub soulpower: SoulPower,
   pub collision_radius: CollideRadius,
+  pub facing: Facing,
+  pub defense: Defense,
+  pub resistances: Resistances,
+  pub poise: Poise,
+  pub stance: Stance,
 
   /// flags
   pub player: Player,
@@ -30,6 +35,14 @@ impl Default for PlayerBundle {
       health: Health { now: 200, max: 200 },
       soulpower: SoulPower { now: 2000, max: 2000 },
       collision_radius: CollideRadius(50.0),
+      facing: Facing(Vec2::X),
+      defense: Defense(3),
+      resistances: Resistances {
+        magic: 0.1,
+        ..Default::default()
+      },
+      poise: Poise(2),
+      stance: Stance::default(),
 
       player: Player,
       player_state: PlayerState::default(),
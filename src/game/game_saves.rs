@@ -1,52 +1,296 @@
+use crate::config::GameConfig;
 use crate::consts::*;
 use crate::crypto::Crypto;
+use crate::crypto::InstallKeyError;
+use crate::input::InputAction;
+use crate::input::InputActions;
+use crate::saves::SaveFile;
 use crate::saves::GameSave;
+use crate::FontAssets;
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
 
 use super::engine::entity::Controlling;
+use super::engine::entity::Player;
+use super::engine::entity::PlayerState;
+use super::engine::entity::Position;
+use super::engine::weapon::Stance;
 use super::entity::monster::MonsterBundle;
 use super::entity::player::PlayerBundle;
 use super::sprite::health::HealthBar;
+use super::stages::SaveLabel;
 
-pub struct AutoSaveSlot(pub u8);
+pub struct AutoSaveSlot(pub u32);
 
 struct AutoSaveTimer(Timer);
 
+/// Write the running game (including a snapshot of the world) into a slot
+pub enum SaveGameEvent {
+  /// the autosave slot
+  Auto,
+  /// the quick save slot of the autosave slot
+  Quick,
+}
+
+/// short message at the bottom right corner, e.g. after a quick save
+pub(super) struct SaveNotice(Timer);
+
+pub(super) fn show_notice(
+  commands: &mut Commands,
+  font_assets: &FontAssets,
+  notice_query: &Query<Entity, With<SaveNotice>>,
+  text: &str,
+) {
+  for entity in notice_query.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+  commands
+    .spawn_bundle(TextBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+          right: Val::Px(20.0),
+          bottom: Val::Px(20.0),
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+      text: Text::with_section(
+        text,
+        TextStyle {
+          font: font_assets.default_font.clone(),
+          font_size: 24.0,
+          color: Color::rgb(0.2, 0.2, 0.2),
+        },
+        Default::default(),
+      ),
+      ..Default::default()
+    })
+    .insert(SaveNotice(Timer::from_seconds(QUICK_SAVE_NOTICE_TIME, false)));
+}
+
+/// shown instead of saving or loading while the installation key is unusable
+fn key_error_notice(key_error: Option<Res<InstallKeyError>>) -> String {
+  match key_error {
+    Some(err) => format!("セーブデータを読み書きできません\n{}", *err),
+    None => "セーブデータを読み書きできません".to_string(),
+  }
+}
+
+fn update_notice(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut query: Query<(Entity, &mut SaveNotice)>,
+) {
+  for (entity, mut notice) in query.iter_mut() {
+    if notice.0.tick(time.delta()).finished() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
+
 fn enter_game(
   mut commands: Commands,
-  save: Res<GameSave>,
+  mut save: ResMut<GameSave>,
   slot: Option<Res<AutoSaveSlot>>,
-  crypto: Res<Crypto>,
+  crypto: Option<Res<Crypto>>,
+  key_error: Option<Res<InstallKeyError>>,
+  config: Res<GameConfig>,
+  font_assets: Res<FontAssets>,
+  notice_query: Query<Entity, With<SaveNotice>>,
 ) {
   if let Some(slot) = slot {
-    save.save(&crypto, slot.0).expect("failed to save!");
-    commands.insert_resource(AutoSaveTimer(Timer::from_seconds(
-      GAME_AUTOSAVE_INTERVAL,
-      true,
-    )));
+    match crypto {
+      Some(crypto) => {
+        save.save(&crypto, &config.save_dir(), SaveFile::Slot(slot.0)).expect("failed to save!");
+        commands.insert_resource(AutoSaveTimer(Timer::from_seconds(
+          GAME_AUTOSAVE_INTERVAL,
+          true,
+        )));
+      }
+      None => show_notice(&mut commands, &font_assets, &notice_query, &key_error_notice(key_error)),
+    }
   }
 
-  // XXX: debug
-  commands.spawn_bundle(PlayerBundle::default()).insert(Controlling);
-  commands.spawn_bundle(MonsterBundle::default()).insert(HealthBar);
+  if let Some(world) = &save.world {
+    world.restore(&mut commands, save.weapon);
+  } else {
+    // XXX: debug
+    commands
+      .spawn_bundle(PlayerBundle {
+        stance: Stance(save.weapon),
+        ..Default::default()
+      })
+      .insert(Controlling);
+    commands.spawn_bundle(MonsterBundle::default()).insert(HealthBar);
+  }
 }
 
 fn update_auto_save(
   time: Res<Time>,
   timer: Option<ResMut<AutoSaveTimer>>,
-  save: Res<GameSave>,
-  slot: Option<Res<AutoSaveSlot>>,
-  crypto: Res<Crypto>,
+  mut events: EventWriter<SaveGameEvent>,
 ) {
   if let Some(mut timer) = timer {
     if timer.0.tick(time.delta()).just_finished() {
-      if let Some(slot) = slot {
-        save.save(&crypto, slot.0).expect("failed to save!");
-      } else {
-        warn!("autosave enabled but save slot not found");
+      events.send(SaveGameEvent::Auto);
+    }
+  }
+}
+
+/// count playing time, states pushed on top of InGame do not count
+fn update_playing_time(time: Res<Time>, mut save: ResMut<GameSave>) {
+  save.total_playing_time += time.delta();
+}
+
+/// save once more before the window gets closed.
+/// `WindowCloseRequested` arrives before `AppExit`, which may come too late in the frame.
+fn save_on_close(
+  mut close_events: EventReader<WindowCloseRequested>,
+  mut exit_events: EventReader<AppExit>,
+  mut events: EventWriter<SaveGameEvent>,
+) {
+  if close_events.iter().count() > 0 || exit_events.iter().count() > 0 {
+    events.send(SaveGameEvent::Auto);
+  }
+}
+
+/// save once more when leaving the game
+fn save_on_exit(mut events: EventWriter<SaveGameEvent>) {
+  events.send(SaveGameEvent::Auto);
+}
+
+/// quick save, unless the player is attacking or assaulting
+fn quick_save(
+  mut commands: Commands,
+  actions: Res<InputActions>,
+  font_assets: Res<FontAssets>,
+  player_query: Query<&PlayerState, (With<Player>, With<Controlling>)>,
+  notice_query: Query<Entity, With<SaveNotice>>,
+  mut events: EventWriter<SaveGameEvent>,
+) {
+  if !actions.just_pressed(InputAction::QuickSave) {
+    return;
+  }
+  if let Ok(state) = player_query.single() {
+    if *state != PlayerState::Stand {
+      show_notice(&mut commands, &font_assets, &notice_query, "攻撃中はセーブできません");
+      return;
+    }
+  }
+  events.send(SaveGameEvent::Quick);
+}
+
+/// replace the running game with the quick save
+fn quick_load(
+  mut commands: Commands,
+  actions: Res<InputActions>,
+  config: Res<GameConfig>,
+  crypto: Option<Res<Crypto>>,
+  key_error: Option<Res<InstallKeyError>>,
+  font_assets: Res<FontAssets>,
+  mut save: ResMut<GameSave>,
+  slot: Option<Res<AutoSaveSlot>>,
+  timer: Option<ResMut<AutoSaveTimer>>,
+  query: Query<Entity, With<Position>>,
+  notice_query: Query<Entity, With<SaveNotice>>,
+) {
+  if !actions.just_pressed(InputAction::QuickLoad) {
+    return;
+  }
+  let slot = match slot {
+    Some(slot) => slot.0,
+    None => return,
+  };
+  let crypto = match crypto {
+    Some(crypto) => crypto,
+    None => {
+      show_notice(&mut commands, &font_assets, &notice_query, &key_error_notice(key_error));
+      return;
+    }
+  };
+
+  let text = match GameSave::load(&crypto, &config.save_dir(), SaveFile::Quick(slot)).save() {
+    Some(quick_save) => {
+      for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
       }
+      *save = quick_save.clone();
+      if let Some(world) = &save.world {
+        world.restore(&mut commands, save.weapon);
+      }
+      if let Some(mut timer) = timer {
+        timer.0.reset();
+      }
+      "クイックロードしました"
+    }
+    None => "クイックセーブがありません",
+  };
+  show_notice(&mut commands, &font_assets, &notice_query, text);
+}
+
+/// clean up the world after the final save
+fn exit_game(
+  mut commands: Commands,
+  query: Query<Entity, With<Position>>,
+  notice_query: Query<Entity, With<SaveNotice>>,
+) {
+  for entity in query.iter().chain(notice_query.iter()) {
+    commands.entity(entity).despawn_recursive();
+  }
+  commands.remove_resource::<AutoSaveTimer>();
+}
+
+fn write_game_save(
+  mut commands: Commands,
+  mut events: EventReader<SaveGameEvent>,
+  mut save: ResMut<GameSave>,
+  slot: Option<Res<AutoSaveSlot>>,
+  crypto: Option<Res<Crypto>>,
+  key_error: Option<Res<InstallKeyError>>,
+  config: Res<GameConfig>,
+  font_assets: Res<FontAssets>,
+  notice_query: Query<Entity, With<SaveNotice>>,
+) {
+  let (mut auto, mut quick) = (false, false);
+  for event in events.iter() {
+    match event {
+      SaveGameEvent::Auto => auto = true,
+      SaveGameEvent::Quick => quick = true,
     }
   }
+  if !auto && !quick {
+    return;
+  }
+  let slot = match slot {
+    Some(slot) => slot.0,
+    None => {
+      warn!("autosave enabled but save slot not found");
+      return;
+    }
+  };
+  let crypto = match crypto {
+    Some(crypto) => crypto,
+    None => {
+      show_notice(&mut commands, &font_assets, &notice_query, &key_error_notice(key_error));
+      return;
+    }
+  };
+
+  if auto {
+    save.save(&crypto, &config.save_dir(), SaveFile::Slot(slot)).expect("failed to save!");
+  }
+  if quick {
+    let text = match save.save(&crypto, &config.save_dir(), SaveFile::Quick(slot)) {
+      Ok(()) => "クイックセーブしました",
+      Err(err) => {
+        warn!("failed to quick save: {}", err);
+        "クイックセーブに失敗しました"
+      }
+    };
+    show_notice(&mut commands, &font_assets, &notice_query, text);
+  }
 }
 
 /// Manage game saves
@@ -55,9 +299,24 @@ pub struct GameSavePlugin;
 impl Plugin for GameSavePlugin {
   fn build(&self, app: &mut App) {
     app
+      .add_event::<SaveGameEvent>()
       .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(enter_game))
       .add_system_set(
-        SystemSet::on_update(AppState::InGame).with_system(update_auto_save),
+        SystemSet::on_update(AppState::InGame)
+          .with_system(update_playing_time.before(SaveLabel::CaptureWorld))
+          .with_system(update_auto_save.before(SaveLabel::CaptureWorld))
+          .with_system(save_on_close.before(SaveLabel::CaptureWorld))
+          .with_system(quick_save.before(SaveLabel::CaptureWorld))
+          .with_system(write_game_save.label(SaveLabel::WriteSave).after(SaveLabel::CaptureWorld))
+          // the world must not change between capturing and writing
+          .with_system(quick_load.after(SaveLabel::WriteSave))
+          .with_system(update_notice),
+      )
+      .add_system_set(
+        SystemSet::on_exit(AppState::InGame)
+          .with_system(save_on_exit.before(SaveLabel::CaptureWorld))
+          .with_system(write_game_save.label(SaveLabel::WriteSave).after(SaveLabel::CaptureWorld))
+          .with_system(exit_game.after(SaveLabel::WriteSave)),
       );
   }
 }
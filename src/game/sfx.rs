@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::{WindowSize, consts::AppState, sounds::{AudioBus, AudioMixer, SoundEffects, SoundOptions}};
+
+use super::{GameCamera, stages::DamageLabel, engine::{attack::{GroupAttack, HitGroupId, HitRegistry, SingleAttack}, entity::Position}};
+
+/// Categories of sounds in combats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatSound {
+  /// an attack is performed
+  Swing,
+  /// an entity recieves damage
+  Hit,
+  /// a projectile is spawned
+  Shoot,
+  CounterAttack,
+  /// health of an entity reaches zero
+  Defeat,
+  SoulGain,
+}
+
+impl CombatSound {
+  /// most instances playing at once,
+  /// attacks sent every frame (like assaults) would clip without it
+  fn limit(&self) -> usize {
+    match self {
+      CombatSound::Swing => 3,
+      CombatSound::Hit => 4,
+      CombatSound::Shoot => 3,
+      CombatSound::CounterAttack => 1,
+      CombatSound::Defeat => 4,
+      CombatSound::SoulGain => 2,
+    }
+  }
+
+  fn source(&self, sounds: &SoundEffects) -> Option<Handle<AudioSource>> {
+    match self {
+      CombatSound::Swing => sounds.swing.clone(),
+      CombatSound::Hit => sounds.hit.clone(),
+      CombatSound::Shoot => sounds.shoot.clone(),
+      CombatSound::CounterAttack => sounds.counter_attack.clone(),
+      CombatSound::Defeat => sounds.defeat.clone(),
+      CombatSound::SoulGain => sounds.soul_gain.clone(),
+    }
+  }
+}
+
+/// Play a combat sound at the position
+pub struct CombatSoundEvent {
+  pub sound: CombatSound,
+  pub position: Vec2,
+}
+
+/// attacks are events already, make sounds of them
+fn attack_sounds(
+  registry: Res<HitRegistry>,
+  mut swung: Local<HashSet<HitGroupId>>,
+  mut group_attacks: EventReader<GroupAttack>,
+  mut single_attacks: EventReader<SingleAttack>,
+  mut sounds: EventWriter<CombatSoundEvent>,
+  query: Query<&Position>,
+) {
+  // a swing sending attacks over several frames makes one sound
+  swung.retain(|id| registry.is_active(*id));
+  for attack in group_attacks.iter() {
+    if let Some(group) = &attack.hit_group {
+      if !swung.insert(group.id) {
+        continue;
+      }
+    }
+    sounds.send(CombatSoundEvent {
+      sound: CombatSound::Swing,
+      position: attack.area.origin(),
+    });
+  }
+  for attack in single_attacks.iter() {
+    if let Ok(position) = query.get(attack.entity) {
+      sounds.send(CombatSoundEvent {
+        sound: CombatSound::Hit,
+        position: position.0,
+      });
+    }
+  }
+}
+
+/// play sounds panned by the position relative to the camera
+fn play_combat_sounds(
+  sfx: Res<SoundEffects>,
+  window_size: Res<WindowSize>,
+  mut mixer: ResMut<AudioMixer>,
+  mut events: EventReader<CombatSoundEvent>,
+  camera_query: Query<&Transform, With<GameCamera>>,
+) {
+  let camera = match camera_query.single() {
+    Ok(transform) => transform.translation.x,
+    Err(_) => 0.0,
+  };
+  for event in events.iter() {
+    let source = match event.sound.source(&sfx) {
+      Some(source) => source,
+      None => continue,
+    };
+    // sounds at the edge of the screen are fully panned
+    let pan = (event.position.x - camera) / (window_size.width / 2.0);
+    mixer.play_with(AudioBus::Sfx, source, SoundOptions {
+      pan: Some(pan.max(-1.0).min(1.0)),
+      limit: Some(event.sound.limit()),
+    });
+  }
+}
+
+pub struct CombatSoundPlugin;
+
+impl Plugin for CombatSoundPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<CombatSoundEvent>()
+      .add_system_set(
+        SystemSet::on_update(AppState::InGame)
+          // hit groups are registered by then
+          .with_system(attack_sounds.after(DamageLabel::Compute))
+          .with_system(play_combat_sounds)
+      );
+  }
+}
@@ -1,16 +1,18 @@
 use bevy::prelude::*;
+use std::collections::HashMap;
 
-use crate::{game::{MouseDirection, engine::entity::{Player, PlayerState, Position, Velocity}, stages::SpriteLabel}};
+use crate::{game::{MouseDirection, engine::{entity::{Player, PlayerState, Position, Velocity}, weapon::{AttackAnimation, Stance, Weapon, Weapons}}, stages::SpriteLabel}};
 
-struct PlayerSprites {
-  stand: Handle<TextureAtlas>,
-  walk: Handle<TextureAtlas>,
-  attack_a: Handle<TextureAtlas>,
-  attack_aa: Handle<TextureAtlas>,
-  attack_ab: Handle<TextureAtlas>,
-  attack_b: Handle<TextureAtlas>,
-  attack_bb: Handle<TextureAtlas>,
-  attack_bbb: Handle<TextureAtlas>,
+/// Sprite sheets of every weapon, by their names in the weapon files
+struct PlayerSprites(HashMap<(Weapon, String), Handle<TextureAtlas>>);
+
+impl PlayerSprites {
+  /// sheets missing in the weapon file fall back to `walk`
+  fn get(&self, weapon: Weapon, name: &str) -> Option<&Handle<TextureAtlas>> {
+    self.0
+      .get(&(weapon, name.to_string()))
+      .or_else(|| self.0.get(&(weapon, "walk".to_string())))
+  }
 }
 
 impl FromWorld for PlayerSprites {
@@ -18,99 +20,55 @@ impl FromWorld for PlayerSprites {
     let cell = world.cell();
     let mut texture_atlases = cell.get_resource_mut::<Assets<TextureAtlas>>().unwrap();
     let asset_server = cell.get_resource::<AssetServer>().unwrap();
+    let weapons = cell.get_resource::<Weapons>().unwrap();
 
-    PlayerSprites {
-      stand: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-stand.png"),
-          Vec2::new(28.0, 30.0),
-          1,
-          1,
-        )
-      ),
-      walk: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-walk.png"),
-          Vec2::new(28.0, 30.0),
-          2,
-          1,
-        )
-      ),
-      attack_a: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-a.png"),
-          Vec2::new(28.0, 30.0),
-          3,
-          1,
-        )
-      ),
-      attack_aa: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-aa.png"),
-          Vec2::new(28.0, 30.0),
-          5,
-          1,
-        )
-      ),
-      attack_ab: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-ab.png"),
-          Vec2::new(28.0, 30.0),
-          2,
-          1,
-        )
-      ),
-      attack_b: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-b.png"),
-          Vec2::new(28.0, 30.0),
-          3,
-          1,
-        )
-      ),
-      attack_bb: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-bb.png"),
-          Vec2::new(28.0, 30.0),
-          3,
-          1,
-        )
-      ),
-      attack_bbb: texture_atlases.add(
-        TextureAtlas::from_grid(
-          asset_server.load("images/char/shuku/shuku-attack-bbb.png"),
-          Vec2::new(28.0, 30.0),
-          5,
-          1,
-        )
-      ),
+    let mut sprites = HashMap::new();
+    for (weapon, def) in weapons.iter() {
+      let (width, height) = def.cell;
+      for (name, sprite) in def.sprites.iter() {
+        let atlas = texture_atlases.add(
+          TextureAtlas::from_grid(
+            asset_server.load(sprite.path.as_str()),
+            Vec2::new(width, height),
+            sprite.columns,
+            1,
+          )
+        );
+        sprites.insert((*weapon, name.clone()), atlas);
+      }
     }
+    PlayerSprites(sprites)
   }
 }
 
 fn change_player_sprite(
   sprites: Res<PlayerSprites>,
-  mut query: Query<(&mut Handle<TextureAtlas>, &Velocity, &PlayerState), (With<Player>, Or<(Changed<Velocity>, Changed<PlayerState>)>)>
+  weapons: Res<Weapons>,
+  mut query: Query<
+    (&mut Handle<TextureAtlas>, &Velocity, &PlayerState, &Stance, Option<&AttackAnimation>),
+    (With<Player>, Or<(Changed<Velocity>, Changed<PlayerState>, Changed<Stance>)>)
+  >
 ) {
-  for (mut handle, velocity, state) in query.iter_mut() {
+  for (mut handle, velocity, state, stance, animation) in query.iter_mut() {
     // TODO: here is alot of more animations needed
-    let new_texture = match state {
-      &PlayerState::Stand => if velocity.0 == Vec2::ZERO {
-        &sprites.stand
+    let name = match state {
+      PlayerState::Stand => if velocity.0 == Vec2::ZERO {
+        "stand"
       } else {
-        &sprites.walk
+        "walk"
       }
-      &PlayerState::ShieldAttackA   => &sprites.attack_a,
-      &PlayerState::ShieldAttackAA  => &sprites.attack_aa,
-      &PlayerState::ShieldAttackAB  => &sprites.attack_ab,
-      &PlayerState::ShieldAttackB   => &sprites.attack_b,
-      &PlayerState::ShieldAttackBB  => &sprites.attack_bb,
-      &PlayerState::ShieldAttackBBB => &sprites.attack_bbb,
-      _ => &sprites.walk,
+      PlayerState::Attack => animation
+        .and_then(|animation| weapons.get(stance.0)?.combos.get(&animation.1))
+        .map(|node| node.sprite.as_str())
+        .unwrap_or("walk"),
+      PlayerState::Assault | PlayerState::AssaultAttack(_) => "assault",
+      PlayerState::Casting => "cast",
     };
 
-    if *handle != *new_texture {
-      *handle = new_texture.clone();
+    if let Some(new_texture) = sprites.get(stance.0, name) {
+      if *handle != *new_texture {
+        *handle = new_texture.clone();
+      }
     }
   }
 }
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::game::stages::SpriteLabel;
 
@@ -48,6 +49,7 @@ fn sprite_sync_rotation(
   }
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct SpriteSize(pub Vec2);
 
 impl Default for SpriteSize {
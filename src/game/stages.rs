@@ -21,6 +21,21 @@ pub enum AttackPriority {
   High,
 }
 
+#[derive(Debug, Hash, Clone, Eq, PartialEq, SystemLabel)]
+/// Steps of the damage pipeline, from `SingleAttack` to `DamageDealt`.
+/// Modifier hooks go to `PreDamage` after `Compute` (edit `PendingDamages`)
+/// or `PostDamage` after `Apply` (read `DamageDealt`).
+pub enum DamageLabel {
+  /// apply defense and resistances
+  Compute,
+  /// hooks before damage is dealt
+  PreDamage,
+  /// reduce health, stagger and knock back
+  Apply,
+  /// hooks after damage is dealt
+  PostDamage,
+}
+
 #[derive(Debug, Hash, Clone, Eq, PartialEq, SystemLabel)]
 pub enum SpriteLabel {
   /// update sprite handle to entities
@@ -29,3 +44,14 @@ pub enum SpriteLabel {
   /// including sprite switching and re-loading.
   SpriteAnimation,
 }
+
+#[derive(Debug, Hash, Clone, Eq, PartialEq, SystemLabel)]
+/// Steps to write the running game into a save, used in both
+/// `on_update` and `on_exit` of `AppState::InGame`.
+/// Systems sending `SaveGameEvent` should run before `CaptureWorld`.
+pub enum SaveLabel {
+  /// capture the live world into `GameSave`
+  CaptureWorld,
+  /// write `GameSave` to disk
+  WriteSave,
+}
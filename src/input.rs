@@ -0,0 +1,381 @@
+use crate::config::GameConfig;
+use crate::consts::AppState;
+use bevy::input::gamepad::{GamepadAxisType, GamepadButtonType, GamepadEvent, GamepadEventType};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Semantic actions of the player, bound in `KeyBindings`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InputAction {
+  Attack1,
+  Attack2,
+  SpecialStatusLaunch,
+  Assault,
+  SwitchWeapon,
+  MoveUpwards,
+  MoveDownwards,
+  MoveLeftwards,
+  MoveRightwards,
+  Interact,
+  OpenHandbook,
+  OpenPlugins,
+  StartMahou,
+  Item1,
+  Item2,
+  Item3,
+  Item4,
+  QuickSave,
+  QuickLoad,
+}
+
+impl InputAction {
+  pub const ALL: [InputAction; 19] = [
+    InputAction::Attack1,
+    InputAction::Attack2,
+    InputAction::SpecialStatusLaunch,
+    InputAction::Assault,
+    InputAction::SwitchWeapon,
+    InputAction::MoveUpwards,
+    InputAction::MoveDownwards,
+    InputAction::MoveLeftwards,
+    InputAction::MoveRightwards,
+    InputAction::Interact,
+    InputAction::OpenHandbook,
+    InputAction::OpenPlugins,
+    InputAction::StartMahou,
+    InputAction::Item1,
+    InputAction::Item2,
+    InputAction::Item3,
+    InputAction::Item4,
+    InputAction::QuickSave,
+    InputAction::QuickLoad,
+  ];
+
+  /// name shown in the settings page
+  #[rustfmt::skip]
+  pub fn title(&self) -> &'static str {
+    use InputAction::*;
+    match self {
+      Attack1 =>             "攻撃1",
+      Attack2 =>             "攻撃2",
+      SpecialStatusLaunch => "特殊状態",
+      Assault =>             "突撃",
+      SwitchWeapon =>        "武器切替",
+      MoveUpwards =>         "上へ移動",
+      MoveDownwards =>       "下へ移動",
+      MoveLeftwards =>       "左へ移動",
+      MoveRightwards =>      "右へ移動",
+      Interact =>            "調べる",
+      OpenHandbook =>        "手帳",
+      OpenPlugins =>         "プラグイン",
+      StartMahou =>          "魔法",
+      Item1 =>               "アイテム1",
+      Item2 =>               "アイテム2",
+      Item3 =>               "アイテム3",
+      Item4 =>               "アイテム4",
+      QuickSave =>           "クイックセーブ",
+      QuickLoad =>           "クイックロード",
+    }
+  }
+}
+
+/// A key or a mouse button
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum InputBinding {
+  Key(KeyCode),
+  Mouse(MouseButton),
+}
+
+impl InputBinding {
+  /// name shown in the settings page
+  pub fn title(&self) -> String {
+    match self {
+      InputBinding::Key(key) => format!("{:?}", key),
+      InputBinding::Mouse(MouseButton::Other(button)) => format!("Mouse {}", button),
+      InputBinding::Mouse(button) => format!("Mouse {:?}", button),
+    }
+  }
+}
+
+/// State of every `InputAction` in this frame.
+/// Gameplay systems should read this instead of `Input<KeyCode>` or `Input<MouseButton>`.
+#[derive(Default)]
+pub struct InputActions {
+  pressed: HashSet<InputAction>,
+  just_pressed: HashSet<InputAction>,
+  just_released: HashSet<InputAction>,
+  /// left stick after the dead zone, length up to 1
+  movement: Vec2,
+  /// right stick direction, if it is tilted beyond the dead zone
+  aim: Option<Vec2>,
+  /// the first connected gamepad
+  gamepad: Option<Gamepad>,
+  /// whether the gamepad has been used more recently than the keyboard and mouse
+  using_gamepad: bool,
+}
+
+impl InputActions {
+  pub fn pressed(&self, action: InputAction) -> bool {
+    self.pressed.contains(&action)
+  }
+
+  pub fn just_pressed(&self, action: InputAction) -> bool {
+    self.just_pressed.contains(&action)
+  }
+
+  pub fn just_released(&self, action: InputAction) -> bool {
+    self.just_released.contains(&action)
+  }
+
+  /// analog movement from the gamepad
+  pub fn movement(&self) -> Vec2 {
+    self.movement
+  }
+
+  /// aiming direction from the gamepad
+  pub fn aim(&self) -> Option<Vec2> {
+    self.aim
+  }
+
+  pub fn using_gamepad(&self) -> bool {
+    self.using_gamepad
+  }
+
+  /// consume the press, so systems running later won't see it
+  pub fn clear_just_pressed(&mut self, action: InputAction) {
+    self.just_pressed.remove(&action);
+  }
+
+  /// consume all presses and releases of this frame
+  pub fn clear(&mut self) {
+    self.just_pressed.clear();
+    self.just_released.clear();
+  }
+}
+
+/// Label of `update_input_actions`, systems reading `InputActions` in `PreUpdate` should run after it
+#[derive(Debug, Hash, Clone, Eq, PartialEq, SystemLabel)]
+pub struct InputActionSystem;
+
+/// read a stick, applying the radial dead zone and the sensitivity
+fn read_stick(
+  axes: &Axis<GamepadAxis>,
+  gamepad: Gamepad,
+  x: GamepadAxisType,
+  y: GamepadAxisType,
+  dead_zone: f32,
+  sensitivity: f32,
+) -> Vec2 {
+  let stick = Vec2::new(
+    axes.get(GamepadAxis(gamepad, x)).unwrap_or(0.0),
+    axes.get(GamepadAxis(gamepad, y)).unwrap_or(0.0),
+  );
+  let length = stick.length();
+  if length <= dead_zone || dead_zone >= 1.0 {
+    return Vec2::ZERO;
+  }
+  let length = ((length - dead_zone) / (1.0 - dead_zone) * sensitivity).min(1.0);
+  stick.normalize() * length
+}
+
+fn update_input_actions(
+  config: Res<GameConfig>,
+  keyboard: Res<Input<KeyCode>>,
+  mouse: Res<Input<MouseButton>>,
+  gamepad_buttons: Res<Input<GamepadButton>>,
+  gamepad_axes: Res<Axis<GamepadAxis>>,
+  mut gamepad_events: EventReader<GamepadEvent>,
+  mut cursor_events: EventReader<CursorMoved>,
+  mut actions: ResMut<InputActions>,
+) {
+  let actions = &mut *actions;
+  actions.pressed.clear();
+  actions.clear();
+
+  for GamepadEvent(gamepad, event) in gamepad_events.iter() {
+    match event {
+      GamepadEventType::Connected if actions.gamepad.is_none() => {
+        info!("gamepad {:?} connected", gamepad);
+        actions.gamepad = Some(*gamepad);
+      }
+      GamepadEventType::Disconnected if actions.gamepad == Some(*gamepad) => {
+        info!("gamepad {:?} disconnected", gamepad);
+        actions.gamepad = None;
+        actions.using_gamepad = false;
+      }
+      _ => {}
+    }
+  }
+
+  let bindings = config.key_bindings();
+  for &action in InputAction::ALL.iter() {
+    let (mut pressed, mut just_pressed, mut just_released) = match bindings.get(action) {
+      InputBinding::Key(key) => (
+        keyboard.pressed(key),
+        keyboard.just_pressed(key),
+        keyboard.just_released(key),
+      ),
+      InputBinding::Mouse(button) => (
+        mouse.pressed(button),
+        mouse.just_pressed(button),
+        mouse.just_released(button),
+      ),
+    };
+    if let (Some(gamepad), Some(button)) = (actions.gamepad, bindings.gamepad.get(action)) {
+      let button = GamepadButton(gamepad, button);
+      pressed |= gamepad_buttons.pressed(button);
+      just_pressed |= gamepad_buttons.just_pressed(button);
+      just_released |= gamepad_buttons.just_released(button);
+    }
+    if pressed {
+      actions.pressed.insert(action);
+    }
+    if just_pressed {
+      actions.just_pressed.insert(action);
+    }
+    if just_released {
+      actions.just_released.insert(action);
+    }
+  }
+
+  actions.movement = Vec2::ZERO;
+  actions.aim = None;
+  if let Some(gamepad) = actions.gamepad {
+    let (dead_zone, sensitivity) = (config.gamepad_dead_zone(), config.gamepad_sensitivity());
+    actions.movement = read_stick(
+      &gamepad_axes,
+      gamepad,
+      GamepadAxisType::LeftStickX,
+      GamepadAxisType::LeftStickY,
+      dead_zone,
+      sensitivity,
+    );
+    let aim = read_stick(
+      &gamepad_axes,
+      gamepad,
+      GamepadAxisType::RightStickX,
+      GamepadAxisType::RightStickY,
+      dead_zone,
+      sensitivity,
+    );
+    if aim != Vec2::ZERO {
+      actions.aim = Some(aim.normalize());
+    }
+
+    if actions.movement != Vec2::ZERO
+      || actions.aim.is_some()
+      || gamepad_buttons.get_just_pressed().next().is_some()
+    {
+      actions.using_gamepad = true;
+    }
+  }
+  if keyboard.get_just_pressed().next().is_some()
+    || mouse.get_just_pressed().next().is_some()
+    || cursor_events.iter().next().is_some()
+  {
+    actions.using_gamepad = false;
+  }
+}
+
+/// Button focused by the D-pad in menus
+#[derive(Default)]
+struct GamepadMenuFocus {
+  entity: Option<Entity>,
+  /// the focused button was clicked in the last frame
+  clicked: bool,
+}
+
+/// move the focus between visible buttons with the D-pad, confirm with the south button.
+/// runs after the UI focus system, so it overrides hovering of the mouse.
+fn navigate_menu_with_gamepad(
+  state: Res<State<AppState>>,
+  actions: Res<InputActions>,
+  gamepad_buttons: Res<Input<GamepadButton>>,
+  mut focus: ResMut<GamepadMenuFocus>,
+  mut query: Query<(Entity, &GlobalTransform, &Node, &mut Interaction), With<Button>>,
+) {
+  // release the click of the last frame
+  if focus.clicked {
+    focus.clicked = false;
+    if let Some(Ok((_, _, _, mut interaction))) = focus.entity.map(|entity| query.get_mut(entity)) {
+      *interaction = Interaction::Hovered;
+    }
+  }
+
+  let gamepad = match actions.gamepad {
+    Some(gamepad) if actions.using_gamepad && *state.current() != AppState::InGame => gamepad,
+    _ => {
+      focus.entity = None;
+      return;
+    }
+  };
+  let just_pressed = |button| gamepad_buttons.just_pressed(GamepadButton(gamepad, button));
+
+  // hidden buttons have no size, sort from top left to bottom right
+  let mut buttons: Vec<(Entity, Vec3)> = query
+    .iter_mut()
+    .filter(|(_, _, node, _)| node.size != Vec2::ZERO)
+    .map(|(entity, transform, _, _)| (entity, transform.translation))
+    .collect();
+  if buttons.is_empty() {
+    focus.entity = None;
+    return;
+  }
+  buttons.sort_by(|(_, a), (_, b)| {
+    b.y.partial_cmp(&a.y).unwrap().then(a.x.partial_cmp(&b.x).unwrap())
+  });
+
+  let current = focus
+    .entity
+    .and_then(|entity| buttons.iter().position(|(e, _)| *e == entity));
+  let next = match current {
+    None => 0,
+    Some(index) if just_pressed(GamepadButtonType::DPadDown) || just_pressed(GamepadButtonType::DPadRight) => {
+      (index + 1) % buttons.len()
+    }
+    Some(index) if just_pressed(GamepadButtonType::DPadUp) || just_pressed(GamepadButtonType::DPadLeft) => {
+      (index + buttons.len() - 1) % buttons.len()
+    }
+    Some(index) => index,
+  };
+
+  let entity = buttons[next].0;
+  if focus.entity != Some(entity) {
+    if let Some(Ok((_, _, _, mut interaction))) = focus.entity.map(|entity| query.get_mut(entity)) {
+      *interaction = Interaction::None;
+    }
+    focus.entity = Some(entity);
+  }
+  if let Ok((_, _, _, mut interaction)) = query.get_mut(entity) {
+    if just_pressed(GamepadButtonType::South) {
+      *interaction = Interaction::Clicked;
+      focus.clicked = true;
+    } else if *interaction != Interaction::Hovered {
+      *interaction = Interaction::Hovered;
+    }
+  }
+}
+
+/// Map raw input to `InputActions` through the key bindings
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<InputActions>()
+      .init_resource::<GamepadMenuFocus>()
+      .add_system_to_stage(
+        CoreStage::PreUpdate,
+        update_input_actions.label(InputActionSystem).after(InputSystem),
+      )
+      .add_system_to_stage(
+        CoreStage::PreUpdate,
+        navigate_menu_with_gamepad
+          .after(InputActionSystem)
+          .after(UiSystem::Focus),
+      );
+  }
+}
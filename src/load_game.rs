@@ -1,10 +1,22 @@
+use crate::config::GameConfig;
 use crate::consts::*;
 use crate::crypto::Crypto;
+use crate::crypto::InstallKeyError;
 use crate::game::AutoSaveSlot;
+use crate::input::InputActions;
+use crate::saves::SaveFile;
+use crate::saves::list_slots;
+use crate::saves::next_free_slot;
 use crate::saves::GameSave;
+use crate::saves::SlotStatus;
 use crate::text_input::TextInputText;
 use crate::FontAssets;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
+use chrono::DateTime;
+use chrono::Local;
+use std::time::Duration;
+use std::time::SystemTime;
 
 struct LoadGameUI;
 struct LoadGameMaterials {
@@ -12,6 +24,7 @@ struct LoadGameMaterials {
   slot_pressed: Handle<ColorMaterial>,
   slot_normal: Handle<ColorMaterial>,
   slot_hover: Handle<ColorMaterial>,
+  dialog_background: Handle<ColorMaterial>,
 }
 
 impl FromWorld for LoadGameMaterials {
@@ -23,22 +36,75 @@ impl FromWorld for LoadGameMaterials {
       slot_pressed: material.add(Color::rgb(0.75, 0.75, 0.75).into()),
       slot_normal: material.add(Color::rgb(0.85, 0.85, 0.85).into()),
       slot_hover: material.add(Color::rgb(0.8, 0.8, 0.8).into()),
+      dialog_background: material.add(Color::rgba(0.9, 0.9, 0.9, 0.95).into()),
     }
   }
 }
 
-struct GameSaveSlot(Option<GameSave>, u8);
+struct GameSaveSlot(SlotStatus, u32);
+
+/// Actions next to each save slot
+enum SlotAction {
+  Rename(u32),
+  Duplicate(u32),
+  Delete(u32),
+}
+
+/// Buttons of the delete confirmation dialog
+enum DeleteDialogButton {
+  Confirm(u32),
+  Cancel,
+}
+
+/// Used to indentify the delete confirmation dialog
+struct DeleteDialogUI;
+
+/// Scrollable list of save slots, with its current scroll offset
+struct SlotListUI(f32);
+
+/// Rebuild the slot list from disk
+struct RefreshSlotList;
+
+/// Order of the slot list
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SlotSortOrder {
+  /// by slot number
+  Slot,
+  /// most recently played first
+  Recent,
+}
+
+impl SlotSortOrder {
+  fn title(&self) -> &'static str {
+    match self {
+      SlotSortOrder::Slot => "sort: slot",
+      SlotSortOrder::Recent => "sort: recently played",
+    }
+  }
+}
+
+/// Used to indentify the sort order button
+struct SortButtonUI;
+
+/// get results after quitting the text input page
+enum LoadGameInputTextReason {
+  /// name of a new game in this slot
+  NewGame(u32),
+  /// new name of an existing slot
+  Rename(u32),
+}
 
 fn make_save_slot(
   parent: &mut ChildBuilder,
   slot: GameSaveSlot,
-  materials: &Res<LoadGameMaterials>,
-  font_assets: &Res<FontAssets>,
+  materials: &LoadGameMaterials,
+  font_assets: &FontAssets,
 ) {
   parent
     .spawn_bundle(ButtonBundle {
       style: Style {
-        size: Size::new(Val::Percent(100.0), Val::Percent(45.0)),
+        size: Size::new(Val::Auto, Val::Percent(100.0)),
+        flex_grow: 1.0,
         align_items: AlignItems::Center,
         justify_content: JustifyContent::Center,
         flex_direction: FlexDirection::ColumnReverse,
@@ -48,42 +114,58 @@ fn make_save_slot(
       ..Default::default()
     })
     .with_children(|parent| {
-      if let Some(save) = &slot.0 {
-        parent.spawn_bundle(TextBundle {
-          text: Text::with_section(
-            save.saving_name.clone(),
-            TextStyle {
-              font: font_assets.default_font.clone(),
-              font_size: 20.0,
-              color: Color::BLACK,
-            },
-            Default::default(),
-          ),
-          ..Default::default()
-        });
-      } else {
-        parent.spawn_bundle(TextBundle {
-          text: Text::with_section(
-            "(empty)",
-            TextStyle {
-              font: font_assets.default_font.clone(),
-              font_size: 20.0,
-              color: Color::BLACK,
-            },
-            Default::default(),
-          ),
-          ..Default::default()
-        });
+      let text_style = TextStyle {
+        font: font_assets.default_font.clone(),
+        font_size: 20.0,
+        color: Color::BLACK,
+      };
+      let hint_style = TextStyle {
+        color: Color::rgb(0.3, 0.3, 0.3),
+        ..text_style.clone()
+      };
+      let warning_style = TextStyle {
+        color: Color::rgb(0.8, 0.2, 0.2),
+        ..text_style.clone()
+      };
+
+      let detail_style = TextStyle {
+        font_size: 16.0,
+        ..hint_style.clone()
+      };
+
+      let lines = match &slot.0 {
+        SlotStatus::Loaded(save) => {
+          let mut lines = vec![(save.saving_name.clone(), text_style)];
+          lines.extend(describe_save(save).into_iter().map(|line| (line, detail_style.clone())));
+          lines
+        }
+        SlotStatus::Recovered(save, index) => {
+          let mut lines = vec![(save.saving_name.clone(), text_style)];
+          lines.extend(describe_save(save).into_iter().map(|line| (line, detail_style.clone())));
+          lines.push((format!("restored from backup {}", index), warning_style));
+          lines
+        }
+        SlotStatus::Empty => vec![
+          ("(empty)".to_string(), text_style),
+          ("click to create new".to_string(), hint_style),
+        ],
+        SlotStatus::Broken => vec![
+          ("(broken)".to_string(), text_style),
+          (format!("moved to {}/, click to create new", SAVE_QUARANTINE_DIR), warning_style),
+        ],
+        SlotStatus::Tampered => vec![
+          ("(tampered)".to_string(), text_style),
+          ("modified outside the game or by another installation".to_string(), warning_style),
+        ],
+        SlotStatus::Unsupported(version) => vec![
+          ("(unsupported)".to_string(), text_style),
+          (format!("saved by a newer version ({})", version), warning_style),
+        ],
+      };
+
+      for (line, style) in lines {
         parent.spawn_bundle(TextBundle {
-          text: Text::with_section(
-            "click to create new",
-            TextStyle {
-              font: font_assets.default_font.clone(),
-              font_size: 20.0,
-              color: Color::rgb(0.3, 0.3, 0.3),
-            },
-            Default::default(),
-          ),
+          text: Text::with_section(line, style, Default::default()),
           ..Default::default()
         });
       }
@@ -91,18 +173,121 @@ fn make_save_slot(
     .insert(slot);
 }
 
+fn format_time(time: SystemTime) -> String {
+  DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn format_duration(duration: Duration) -> String {
+  let secs = duration.as_secs();
+  format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// preview lines below the saving name
+fn describe_save(save: &GameSave) -> Vec<String> {
+  vec![
+    format!("{}  |  played {}", save.area, format_duration(save.total_playing_time)),
+    format!(
+      "HP {}/{}  EN {}/{}  money {}",
+      save.health, save.health_limit, save.energy, save.energy_limit, save.money
+    ),
+    format!(
+      "created {}  |  last played {}",
+      format_time(save.created_time),
+      format_time(save.last_modified_time)
+    ),
+  ]
+}
+
+fn make_slot_row(
+  parent: &mut ChildBuilder,
+  slot: GameSaveSlot,
+  materials: &LoadGameMaterials,
+  font_assets: &FontAssets,
+) {
+  let text_style = TextStyle {
+    font: font_assets.default_font.clone(),
+    font_size: 20.0,
+    color: Color::BLACK,
+  };
+  let mut actions = Vec::new();
+  if slot.0.save().is_some() {
+    actions.push((SlotAction::Rename(slot.1), "rename"));
+    actions.push((SlotAction::Duplicate(slot.1), "duplicate"));
+  }
+  if !matches!(slot.0, SlotStatus::Empty) {
+    actions.push((SlotAction::Delete(slot.1), "delete"));
+  }
+
+  parent
+    .spawn_bundle(NodeBundle {
+      style: Style {
+        size: Size::new(Val::Percent(100.0), Val::Px(SAVE_SLOT_HEIGHT)),
+        margin: Rect {
+          bottom: Val::Px(10.0),
+          ..Default::default()
+        },
+        flex_shrink: 0.0,
+        ..Default::default()
+      },
+      material: materials.transparent.clone(),
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      make_save_slot(parent, slot, materials, font_assets);
+
+      // actions
+      parent
+        .spawn_bundle(NodeBundle {
+          style: Style {
+            size: Size::new(Val::Px(120.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::SpaceAround,
+            ..Default::default()
+          },
+          material: materials.transparent.clone(),
+          ..Default::default()
+        })
+        .with_children(|parent| {
+          for (action, title) in actions {
+            parent
+              .spawn_bundle(ButtonBundle {
+                style: Style {
+                  size: Size::new(Val::Percent(100.0), Val::Px(25.0)),
+                  margin: Rect {
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                  },
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  ..Default::default()
+                },
+                material: materials.slot_normal.clone(),
+                ..Default::default()
+              })
+              .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                  text: Text::with_section(title, text_style.clone(), Default::default()),
+                  ..Default::default()
+                });
+              })
+              .insert(action);
+          }
+        });
+    });
+}
+
 fn setup_load_game(
   mut commands: Commands,
   font_assets: Res<FontAssets>,
   materials: Res<LoadGameMaterials>,
-  crypto: Res<Crypto>,
+  order: Res<SlotSortOrder>,
 ) {
   commands
     .spawn_bundle(NodeBundle {
       style: Style {
         size: Size::new(Val::Percent(80.0), Val::Percent(80.0)),
         margin: Rect::all(Val::Auto),
-        justify_content: JustifyContent::SpaceBetween,
+        flex_direction: FlexDirection::ColumnReverse,
         ..Default::default()
       },
       material: materials.transparent.clone(),
@@ -110,59 +295,190 @@ fn setup_load_game(
     })
     .insert(LoadGameUI)
     .with_children(|parent| {
-      // left
+      // sort order
       parent
-        .spawn_bundle(NodeBundle {
+        .spawn_bundle(ButtonBundle {
           style: Style {
-            size: Size::new(Val::Percent(45.0), Val::Percent(100.0)),
-            flex_direction: FlexDirection::ColumnReverse,
-            justify_content: JustifyContent::SpaceBetween,
+            size: Size::new(Val::Px(250.0), Val::Px(30.0)),
+            margin: Rect {
+              bottom: Val::Px(10.0),
+              ..Default::default()
+            },
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_shrink: 0.0,
             ..Default::default()
           },
-          material: materials.transparent.clone(),
+          material: materials.slot_normal.clone(),
           ..Default::default()
         })
         .with_children(|parent| {
-          make_save_slot(
-            parent,
-            GameSaveSlot(GameSave::load(&crypto, 0), 0),
-            &materials,
-            &font_assets,
-          );
-          make_save_slot(
-            parent,
-            GameSaveSlot(GameSave::load(&crypto, 2), 2),
-            &materials,
-            &font_assets,
-          );
-        });
-      // right
+          parent.spawn_bundle(TextBundle {
+            text: Text::with_section(
+              order.title(),
+              TextStyle {
+                font: font_assets.default_font.clone(),
+                font_size: 20.0,
+                color: Color::BLACK,
+              },
+              Default::default(),
+            ),
+            ..Default::default()
+          });
+        })
+        .insert(SortButtonUI);
+
+      // viewport of the slot list
       parent
         .spawn_bundle(NodeBundle {
           style: Style {
-            size: Size::new(Val::Percent(45.0), Val::Percent(100.0)),
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            flex_grow: 1.0,
             flex_direction: FlexDirection::ColumnReverse,
-            justify_content: JustifyContent::SpaceBetween,
+            overflow: Overflow::Hidden,
             ..Default::default()
           },
           material: materials.transparent.clone(),
           ..Default::default()
         })
         .with_children(|parent| {
-          make_save_slot(
-            parent,
-            GameSaveSlot(GameSave::load(&crypto, 1), 1),
-            &materials,
-            &font_assets,
-          );
-          make_save_slot(
-            parent,
-            GameSaveSlot(GameSave::load(&crypto, 3), 3),
-            &materials,
-            &font_assets,
-          );
+          parent
+            .spawn_bundle(NodeBundle {
+              style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                flex_shrink: 0.0,
+                ..Default::default()
+              },
+              material: materials.transparent.clone(),
+              ..Default::default()
+            })
+            .insert(SlotListUI(0.0));
+        });
+    });
+  commands.insert_resource(RefreshSlotList);
+}
+
+/// switch the order of the slot list
+fn sort_button_click(
+  mut commands: Commands,
+  mut order: ResMut<SlotSortOrder>,
+  query: Query<(&Interaction, &Children), (Changed<Interaction>, With<SortButtonUI>)>,
+  mut text_query: Query<&mut Text>,
+) {
+  for (interaction, children) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    *order = match *order {
+      SlotSortOrder::Slot => SlotSortOrder::Recent,
+      SlotSortOrder::Recent => SlotSortOrder::Slot,
+    };
+    for child in children.iter() {
+      if let Ok(mut text) = text_query.get_mut(*child) {
+        text.sections[0].value = order.title().to_string();
+      }
+    }
+    commands.insert_resource(RefreshSlotList);
+  }
+}
+
+/// (re)build rows of the slot list, with an empty slot at the end
+fn refresh_slot_list(
+  mut commands: Commands,
+  refresh: Option<Res<RefreshSlotList>>,
+  font_assets: Res<FontAssets>,
+  materials: Res<LoadGameMaterials>,
+  crypto: Option<Res<Crypto>>,
+  key_error: Option<Res<InstallKeyError>>,
+  config: Res<GameConfig>,
+  order: Res<SlotSortOrder>,
+  list_query: Query<(Entity, Option<&Children>), With<SlotListUI>>,
+) {
+  if refresh.is_none() {
+    return;
+  }
+  // wait until the list has been spawned
+  let (list, children) = match list_query.single() {
+    Ok(list) => list,
+    Err(_) => return,
+  };
+  commands.remove_resource::<RefreshSlotList>();
+
+  if let Some(children) = children {
+    for child in children.iter() {
+      commands.entity(*child).despawn_recursive();
+    }
+  }
+
+  let crypto = match crypto {
+    Some(crypto) => crypto,
+    None => {
+      // no slot is shown, so nothing can be loaded or saved
+      let message = key_error.map_or(String::new(), |err| err.to_string());
+      error!("saves disabled: {}", message);
+      commands.entity(list).with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+          text: Text::with_section(
+            format!("セーブデータを読み書きできません\n{}", message),
+            TextStyle {
+              font: font_assets.default_font.clone(),
+              font_size: 20.0,
+              color: Color::rgb(0.8, 0.2, 0.2),
+            },
+            Default::default(),
+          ),
+          ..Default::default()
         });
+      });
+      return;
+    }
+  };
+
+  let save_dir = config.save_dir();
+  let mut slots: Vec<GameSaveSlot> = list_slots(&save_dir)
+    .into_iter()
+    .map(|slot| GameSaveSlot(GameSave::load(&crypto, &save_dir, SaveFile::Slot(slot)), slot))
+    .collect();
+  if *order == SlotSortOrder::Recent {
+    // slots without a readable save go last
+    slots.sort_by_key(|slot| {
+      std::cmp::Reverse(slot.0.save().map(|save| save.last_modified_time))
     });
+  }
+  slots.push(GameSaveSlot(SlotStatus::Empty, next_free_slot(&save_dir)));
+
+  commands.entity(list).with_children(|parent| {
+    for slot in slots {
+      make_slot_row(parent, slot, &materials, &font_assets);
+    }
+  });
+}
+
+/// scroll the slot list with the mouse wheel
+fn scroll_slot_list(
+  mut events: EventReader<MouseWheel>,
+  mut query: Query<(&mut SlotListUI, &mut Style, &Node, &Parent)>,
+  node_query: Query<&Node>,
+) {
+  let delta: f32 = events
+    .iter()
+    .map(|event| match event.unit {
+      MouseScrollUnit::Line => event.y * SAVE_SLOT_HEIGHT,
+      MouseScrollUnit::Pixel => event.y,
+    })
+    .sum();
+  if delta == 0.0 {
+    return;
+  }
+
+  for (mut list, mut style, node, parent) in query.iter_mut() {
+    let viewport = node_query.get(parent.0).map(|node| node.size.y).unwrap_or(0.0);
+    let max_offset = (node.size.y - viewport).max(0.0);
+    // UI y-axis points upwards, move the list up to show the rows below
+    list.0 = (list.0 - delta).max(0.0).min(max_offset);
+    style.position.top = Val::Px(list.0);
+  }
 }
 
 fn slot_material_change(
@@ -183,21 +499,33 @@ fn slot_material_change(
 
 fn slot_button_click(
   mut mouse_input: ResMut<Input<MouseButton>>,
+  mut actions: ResMut<InputActions>,
   mut commands: Commands,
   mut interaction_query: Query<(&Interaction, &GameSaveSlot), (Changed<Interaction>, With<Button>)>,
+  dialog_query: Query<Entity, With<DeleteDialogUI>>,
   mut state: ResMut<State<AppState>>,
 ) {
+  // the dialog is on top of everything
+  if dialog_query.iter().next().is_some() {
+    return;
+  }
   for (interaction, save_slot) in interaction_query.iter_mut() {
     match *interaction {
       Interaction::Clicked => {
-        commands.insert_resource(AutoSaveSlot(save_slot.1));
-        if let Some(save) = &save_slot.0 {
+        if let SlotStatus::Unsupported(_) | SlotStatus::Tampered = save_slot.0 {
+          // never overwrite saves from a newer game or signed by another key
+          continue;
+        }
+        if let Some(save) = save_slot.0.save() {
           // start the game directly
           mouse_input.clear_just_pressed(MouseButton::Left);
+          actions.clear();
+          commands.insert_resource(AutoSaveSlot(save_slot.1));
           commands.insert_resource(save.clone());
           state.replace(AppState::InGame).unwrap();
         } else {
           // start the game after type its saving name
+          commands.insert_resource(LoadGameInputTextReason::NewGame(save_slot.1));
           state.push(AppState::TextInput).unwrap();
         }
       }
@@ -206,6 +534,154 @@ fn slot_button_click(
   }
 }
 
+fn slot_action_click(
+  mut commands: Commands,
+  mut state: ResMut<State<AppState>>,
+  font_assets: Res<FontAssets>,
+  materials: Res<LoadGameMaterials>,
+  crypto: Option<Res<Crypto>>,
+  config: Res<GameConfig>,
+  query: Query<(&Interaction, &SlotAction), Changed<Interaction>>,
+  root_query: Query<Entity, With<LoadGameUI>>,
+  dialog_query: Query<Entity, With<DeleteDialogUI>>,
+) {
+  if dialog_query.iter().next().is_some() {
+    return;
+  }
+  let crypto = match crypto {
+    Some(crypto) => crypto,
+    None => return,
+  };
+  let save_dir = config.save_dir();
+  for (interaction, action) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    match *action {
+      SlotAction::Rename(slot) => {
+        if let Some(save) = GameSave::load(&crypto, &save_dir, SaveFile::Slot(slot)).save() {
+          commands.insert_resource(TextInputText(save.saving_name.clone()));
+          commands.insert_resource(LoadGameInputTextReason::Rename(slot));
+          state.push(AppState::TextInput).unwrap();
+        }
+      }
+      SlotAction::Duplicate(slot) => {
+        if let Some(save) = GameSave::load(&crypto, &save_dir, SaveFile::Slot(slot)).save() {
+          let mut save = save.clone();
+          save.saving_name = format!("{} (copy)", save.saving_name);
+          let target = next_free_slot(&save_dir);
+          if let Err(err) = save.save(&crypto, &save_dir, SaveFile::Slot(target)) {
+            warn!("failed to duplicate slot {}: {}", slot, err);
+          }
+          commands.insert_resource(RefreshSlotList);
+        }
+      }
+      SlotAction::Delete(slot) => {
+        for root in root_query.iter() {
+          commands.entity(root).with_children(|parent| {
+            spawn_delete_dialog(parent, slot, &materials, &font_assets);
+          });
+        }
+      }
+    }
+  }
+}
+
+fn spawn_delete_dialog(
+  parent: &mut ChildBuilder,
+  slot: u32,
+  materials: &LoadGameMaterials,
+  font_assets: &FontAssets,
+) {
+  let text_style = TextStyle {
+    font: font_assets.default_font.clone(),
+    font_size: 24.0,
+    color: Color::BLACK,
+  };
+
+  parent
+    .spawn_bundle(NodeBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+          left: Val::Percent(20.0),
+          top: Val::Percent(35.0),
+          ..Default::default()
+        },
+        size: Size::new(Val::Percent(60.0), Val::Percent(30.0)),
+        flex_direction: FlexDirection::ColumnReverse,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::SpaceAround,
+        ..Default::default()
+      },
+      material: materials.dialog_background.clone(),
+      ..Default::default()
+    })
+    .insert(DeleteDialogUI)
+    .with_children(|parent| {
+      parent.spawn_bundle(TextBundle {
+        text: Text::with_section(
+          format!("Delete slot {}? This can not be undone.", slot),
+          text_style.clone(),
+          Default::default(),
+        ),
+        ..Default::default()
+      });
+      parent
+        .spawn_bundle(NodeBundle {
+          material: materials.transparent.clone(),
+          ..Default::default()
+        })
+        .with_children(|parent| {
+          for (button, title) in vec![(DeleteDialogButton::Confirm(slot), "delete"), (DeleteDialogButton::Cancel, "cancel")] {
+            parent
+              .spawn_bundle(ButtonBundle {
+                style: Style {
+                  size: Size::new(Val::Px(150.0), Val::Px(40.0)),
+                  margin: Rect::all(Val::Px(10.0)),
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  ..Default::default()
+                },
+                material: materials.slot_normal.clone(),
+                ..Default::default()
+              })
+              .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                  text: Text::with_section(title, text_style.clone(), Default::default()),
+                  ..Default::default()
+                });
+              })
+              .insert(button);
+          }
+        });
+    });
+}
+
+fn delete_dialog_click(
+  mut commands: Commands,
+  config: Res<GameConfig>,
+  query: Query<(&Interaction, &DeleteDialogButton), Changed<Interaction>>,
+  dialog_query: Query<Entity, With<DeleteDialogUI>>,
+) {
+  for (interaction, button) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    if let DeleteDialogButton::Confirm(slot) = *button {
+      for file in vec![SaveFile::Slot(slot), SaveFile::Quick(slot)] {
+        if let Err(err) = GameSave::delete(&config.save_dir(), file) {
+          warn!("failed to delete {}: {}", file, err);
+        }
+      }
+      commands.insert_resource(RefreshSlotList);
+    }
+    for entity in dialog_query.iter() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
+
 fn hide_ui(mut query: Query<&mut Style, With<LoadGameUI>>) {
   for mut style in query.iter_mut() {
     style.display = Display::None;
@@ -214,12 +690,44 @@ fn hide_ui(mut query: Query<&mut Style, With<LoadGameUI>>) {
 
 fn resume_game(
   mut commands: Commands,
+  reason: Option<Res<LoadGameInputTextReason>>,
   text: Option<Res<TextInputText>>,
+  crypto: Option<Res<Crypto>>,
+  config: Res<GameConfig>,
   mut state: ResMut<State<AppState>>,
 ) {
-  if let Some(text) = text {
-    commands.insert_resource(GameSave::new(text.0.clone()));
-    state.replace(AppState::InGame).unwrap();
+  let reason = match reason {
+    Some(reason) => reason,
+    None => return,
+  };
+  commands.remove_resource::<LoadGameInputTextReason>();
+  let text = match text {
+    Some(text) => text,
+    None => return,
+  };
+  commands.remove_resource::<TextInputText>();
+
+  match *reason {
+    LoadGameInputTextReason::NewGame(slot) => {
+      commands.insert_resource(AutoSaveSlot(slot));
+      commands.insert_resource(GameSave::new(text.0.clone()));
+      state.replace(AppState::InGame).unwrap();
+    }
+    LoadGameInputTextReason::Rename(slot) => {
+      let crypto = match crypto {
+        Some(crypto) => crypto,
+        None => return,
+      };
+      let save_dir = config.save_dir();
+      if let Some(save) = GameSave::load(&crypto, &save_dir, SaveFile::Slot(slot)).save() {
+        let mut save = save.clone();
+        save.saving_name = text.0.clone();
+        if let Err(err) = save.save(&crypto, &save_dir, SaveFile::Slot(slot)) {
+          warn!("failed to rename slot {}: {}", slot, err);
+        }
+      }
+      commands.insert_resource(RefreshSlotList);
+    }
   }
 }
 
@@ -233,6 +741,7 @@ fn destroy_load_game(mut commands: Commands, query: Query<Entity, With<LoadGameU
   for entity in query.iter() {
     commands.entity(entity).despawn_recursive();
   }
+  commands.remove_resource::<RefreshSlotList>();
 }
 
 pub struct LoadGamePlugin;
@@ -241,11 +750,17 @@ impl Plugin for LoadGamePlugin {
   fn build(&self, app: &mut App) {
     app
       .init_resource::<LoadGameMaterials>()
+      .insert_resource(SlotSortOrder::Slot)
       .add_system_set(SystemSet::on_enter(AppState::LoadGame).with_system(setup_load_game))
       .add_system_set(
         SystemSet::on_update(AppState::LoadGame)
+          .with_system(refresh_slot_list)
+          .with_system(scroll_slot_list)
+          .with_system(sort_button_click)
           .with_system(slot_material_change)
-          .with_system(slot_button_click),
+          .with_system(slot_button_click)
+          .with_system(slot_action_click)
+          .with_system(delete_dialog_click),
       )
       .add_system_set(
         SystemSet::on_exit(AppState::LoadGame).with_system(destroy_load_game),
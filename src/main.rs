@@ -7,13 +7,18 @@ use bevy::window::WindowResized;
 use bevy::{prelude::*, render::pass::ClearColor};
 use game::GameCamera;
 
+mod assets;
 mod config;
 mod consts;
 mod crypto;
+mod display;
 mod game;
+mod input;
 mod load_game;
 mod logo;
 mod menu;
+mod music;
+mod save_tool;
 mod saves;
 mod settings;
 mod staff;
@@ -23,12 +28,15 @@ mod sounds;
 use crate::config::GameConfig;
 use crate::consts::*;
 use crate::crypto::Crypto;
+use crate::display::DisplayPlugin;
 use crate::game::GameBasicPlugins;
 use crate::game::GameSystemPlugins;
 use crate::game::SpriteSystemPlugins;
+use crate::input::InputActionPlugin;
 use crate::load_game::LoadGamePlugin;
 use crate::logo::StudioLogoPlugin;
 use crate::menu::GameMenuPlugin;
+use crate::music::MusicPlugin;
 use crate::settings::SettingsPlugin;
 use crate::staff::StaffPlugin;
 use crate::text_input::TextInputPlugin;
@@ -91,12 +99,24 @@ fn issue_1135_system(mut query: Query<(&Node, &mut Visible), With<Text>>) {
 }
 
 fn main() {
+  // QA tool for save files, see `save_tool.rs`
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(|arg| arg.as_str()) == Some("--save-tool") {
+    std::process::exit(save_tool::run(&args[1..]));
+  }
+
   let game_config = GameConfig::load();
   let window_descriptor = game_config.get_window_descriptor();
 
-  App::new()
+  let mut app = App::new();
+  // without the installation key saves are neither loaded nor written,
+  // the load game page shows the error instead
+  match Crypto::from_installation() {
+    Ok(crypto) => app.insert_resource(crypto),
+    Err(err) => app.insert_resource(err),
+  };
+  app
     .insert_resource(ClearColor(Color::rgb(0.9, 0.9, 0.9)))
-    .insert_resource(Crypto::new(CRYPTO_KEY))
     .insert_resource(MousePosition(Vec2::ZERO))
     .insert_resource(WindowSize {
       width: window_descriptor.width,
@@ -109,6 +129,8 @@ fn main() {
     .add_system_to_stage(CoreStage::PostUpdate, issue_1135_system)
     .add_plugins(DefaultPlugins)
     .init_resource::<FontAssets>()
+    .add_plugin(InputActionPlugin)
+    .add_plugin(DisplayPlugin)
     .add_plugin(StudioLogoPlugin)
     .add_plugin(GameMenuPlugin)
     .add_plugin(StaffPlugin)
@@ -116,6 +138,7 @@ fn main() {
     .add_plugin(TextInputPlugin)
     .add_plugin(SettingsPlugin)
     .add_plugin(SoundEffectsPlugin)
+    .add_plugin(MusicPlugin)
     .add_plugins(GameBasicPlugins)
     .add_plugins(GameSystemPlugins)
     .add_plugins(SpriteSystemPlugins)
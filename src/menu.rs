@@ -1,6 +1,6 @@
 use crate::consts::*;
 use crate::FontAssets;
-use crate::sounds::SoundEffects;
+use crate::sounds::{AudioBus, AudioMixer, SoundEffects};
 use bevy::app::AppExit;
 use bevy::prelude::*;
 
@@ -147,12 +147,12 @@ fn button_click(
 
 fn button_hover_sfx(
   sounds: Res<SoundEffects>,
-  audio: Res<Audio>,
+  mut mixer: ResMut<AudioMixer>,
   query: Query<&Interaction, (Changed<Interaction>, With<MenuButton>)>,
 ) {
   for interaction in query.iter() {
     if interaction == &Interaction::Hovered {
-      audio.play(sounds.tape.clone());
+      mixer.play(AudioBus::Sfx, sounds.tape.clone());
     }
   }
 }
@@ -0,0 +1,156 @@
+use crate::assets::asset_path;
+use crate::consts::*;
+use crate::sounds::{AudioBus, AudioMixer, AudioOutput};
+use bevy::asset::LoadState;
+use bevy::ecs::system::{NonSend, NonSendMut};
+use bevy::prelude::*;
+use rodio::Sink;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::read;
+
+/// Tracks of every state, read from `assets/music.toml`
+#[derive(Deserialize, Default)]
+pub struct MusicTracks {
+  /// seconds to fade between tracks
+  crossfade: f32,
+  /// playlists keyed by the name of `AppState`
+  states: HashMap<String, Vec<String>>,
+}
+
+impl MusicTracks {
+  /// relative to `assets`, like the tracks listed in it
+  const TRACKS_FILE: &'static str = "music.toml";
+
+  pub fn load() -> MusicTracks {
+    let path = asset_path(MusicTracks::TRACKS_FILE);
+    if let Ok(data) = read(&path) {
+      match toml::from_slice::<MusicTracks>(&data[..]) {
+        Ok(tracks) => {
+          if tracks.states.values().all(|playlist| playlist.is_empty()) {
+            info!("no music assigned in {}", path.display());
+          }
+          return tracks;
+        }
+        Err(err) => warn!("failed to parse {}: {}", path.display(), err),
+      }
+    }
+    warn!("no music tracks loaded from {}", path.display());
+    MusicTracks::default()
+  }
+
+  /// playlist of the state, `None` if the state does not change the music
+  pub fn get(&self, state: &AppState) -> Option<&Vec<String>> {
+    self.states.get(&format!("{:?}", state))
+  }
+}
+
+/// A track playing on the output
+struct MusicTrack {
+  sink: Sink,
+  /// fading gain, ranged from 0.0 to 1.0
+  gain: f32,
+}
+
+/// The playlist being played
+#[derive(Default)]
+struct MusicPlayer {
+  playlist: Vec<String>,
+  index: usize,
+  /// the next track waiting to be loaded
+  pending: Option<Handle<AudioSource>>,
+  /// tracks failed to load in a row, the playlist stops once all of them failed
+  failures: usize,
+  current: Option<MusicTrack>,
+  /// tracks fading out
+  fading: Vec<MusicTrack>,
+}
+
+impl MusicPlayer {
+  /// fade out the current track and load the track at `index`
+  fn switch_to(&mut self, index: usize, asset_server: &AssetServer) {
+    if let Some(track) = self.current.take() {
+      self.fading.push(track);
+    }
+    self.index = index;
+    self.pending = self
+      .playlist
+      .get(index)
+      .map(|path| asset_server.load(path.as_str()));
+  }
+}
+
+/// follow the state, start loaded tracks and fade them
+fn update_music(
+  time: Res<Time>,
+  state: Res<State<AppState>>,
+  tracks: Res<MusicTracks>,
+  mixer: Res<AudioMixer>,
+  asset_server: Res<AssetServer>,
+  sources: Res<Assets<AudioSource>>,
+  output: NonSend<AudioOutput>,
+  mut player: NonSendMut<MusicPlayer>,
+) {
+  if let Some(playlist) = tracks.get(state.current()) {
+    if *playlist != player.playlist {
+      player.playlist = playlist.clone();
+      player.failures = 0;
+      player.switch_to(0, &asset_server);
+    }
+  }
+
+  // loop the playlist when the current track ends
+  let ended = matches!(&player.current, Some(track) if track.sink.empty());
+  if ended {
+    let next = (player.index + 1) % player.playlist.len();
+    player.current = None;
+    player.switch_to(next, &asset_server);
+  }
+
+  if let Some(handle) = player.pending.clone() {
+    if let Some(source) = sources.get(&handle) {
+      player.pending = None;
+      player.failures = 0;
+      player.current = output
+        .play(source, 0.0)
+        .map(|sink| MusicTrack { sink, gain: 0.0 });
+    } else if asset_server.get_load_state(&handle) == LoadState::Failed {
+      warn!("failed to load music {}", player.playlist[player.index]);
+      player.failures += 1;
+      if player.failures < player.playlist.len() {
+        let next = (player.index + 1) % player.playlist.len();
+        player.switch_to(next, &asset_server);
+      } else {
+        player.pending = None;
+      }
+    }
+  }
+
+  let step = if tracks.crossfade > 0.0 {
+    time.delta_seconds() / tracks.crossfade
+  } else {
+    1.0
+  };
+  let volume = mixer.volumes().get(AudioBus::Music);
+  if let Some(track) = player.current.as_mut() {
+    track.gain = (track.gain + step).min(1.0);
+    track.sink.set_volume(track.gain * volume);
+  }
+  player.fading.retain(|track| track.gain > 0.0 && !track.sink.empty());
+  for track in player.fading.iter_mut() {
+    track.gain = (track.gain - step).max(0.0);
+    track.sink.set_volume(track.gain * volume);
+  }
+}
+
+/// Background music of each state
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(MusicTracks::load())
+      .init_non_send_resource::<MusicPlayer>()
+      .add_system_to_stage(CoreStage::PostUpdate, update_music);
+  }
+}
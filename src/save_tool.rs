@@ -0,0 +1,157 @@
+use crate::config::GameConfig;
+use crate::crypto::Crypto;
+use crate::saves::list_save_files;
+use crate::saves::list_slots;
+use crate::saves::GameSave;
+use crate::saves::SaveFile;
+use std::fs::read_to_string;
+use std::fs::write;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+usage: upstream --save-tool [--dir <save dir>] <command>
+
+commands:
+  list                                     list slots and their metadata
+  validate                                 try to decode every save file and backup
+  dump <slot> [--toml] [--output <file>]   decrypt a slot into json, or toml with --toml / a .toml output
+  import <slot> <file>                     encrypt an edited json / toml file into a slot
+
+the save directory defaults to the one in the game config.";
+
+/// Output formats of `dump` and `import`
+enum Format {
+  Json,
+  Toml,
+}
+
+impl Format {
+  fn from_path(path: &str) -> Format {
+    if path.ends_with(".toml") {
+      Format::Toml
+    } else {
+      Format::Json
+    }
+  }
+}
+
+/// Entrance of the save tool, returns the exit code
+pub fn run(args: &[String]) -> i32 {
+  match execute(args) {
+    Ok(_) => 0,
+    Err(message) => {
+      eprintln!("{}", message);
+      1
+    }
+  }
+}
+
+fn execute(args: &[String]) -> Result<(), String> {
+  let mut args = args.iter().map(|arg| arg.as_str()).peekable();
+  let mut save_dir = GameConfig::load().save_dir();
+  if args.peek() == Some(&"--dir") {
+    args.next();
+    save_dir = PathBuf::from(args.next().ok_or(USAGE)?);
+  }
+  let crypto = Crypto::from_installation().map_err(|err| err.to_string())?;
+
+  match args.next() {
+    Some("list") => {
+      for slot in list_slots(&save_dir) {
+        match GameSave::read(&crypto, &save_dir, SaveFile::Slot(slot)) {
+          Ok(save) => println!(
+            "slot {}: {:?} area={:?} played={}s health={}/{} energy={}/{} money={}",
+            slot,
+            save.saving_name,
+            save.area,
+            save.total_playing_time.as_secs(),
+            save.health,
+            save.health_limit,
+            save.energy,
+            save.energy_limit,
+            save.money,
+          ),
+          Err(err) => println!("slot {}: {}", slot, err),
+        }
+      }
+      Ok(())
+    }
+    Some("validate") => {
+      let mut broken = 0;
+      for path in list_save_files(&save_dir) {
+        let result = std::fs::read(&path)
+          .map_err(|err| err.to_string())
+          .and_then(|data| GameSave::decode(&crypto, &data).map_err(|err| err.to_string()));
+        match result {
+          Ok(_) => println!("ok      {}", path.display()),
+          Err(err) => {
+            broken += 1;
+            println!("broken  {}: {}", path.display(), err);
+          }
+        }
+      }
+      if broken > 0 {
+        Err(format!("{} broken save files", broken))
+      } else {
+        Ok(())
+      }
+    }
+    Some("dump") => {
+      let slot = parse_slot(args.next())?;
+      let mut toml = false;
+      let mut output = None;
+      while let Some(arg) = args.next() {
+        match arg {
+          "--toml" => toml = true,
+          "--output" => output = Some(args.next().ok_or(USAGE)?),
+          _ => return Err(USAGE.to_string()),
+        }
+      }
+      // follow the extension like `import` does, so that the file can be imported back
+      let format = match output {
+        Some(path) if toml && path.ends_with(".json") => {
+          return Err(format!("--toml can not be written into {}", path))
+        }
+        _ if toml => Format::Toml,
+        Some(path) => Format::from_path(path),
+        None => Format::Json,
+      };
+      let save = GameSave::read(&crypto, &save_dir, SaveFile::Slot(slot)).map_err(|err| err.to_string())?;
+      let text = match format {
+        Format::Json => serde_json::to_string_pretty(&save).map_err(|err| err.to_string())?,
+        // convert into a value first, otherwise toml complains about values after tables
+        Format::Toml => toml::Value::try_from(&save)
+          .and_then(|value| toml::to_string_pretty(&value))
+          .map_err(|err| err.to_string())?,
+      };
+      match output {
+        Some(path) => write(path, text).map_err(|err| err.to_string()),
+        None => {
+          println!("{}", text);
+          Ok(())
+        }
+      }
+    }
+    Some("import") => {
+      let slot = parse_slot(args.next())?;
+      let path = args.next().ok_or(USAGE)?;
+      let text = read_to_string(path).map_err(|err| err.to_string())?;
+      let save: GameSave = match Format::from_path(path) {
+        Format::Json => serde_json::from_str(&text).map_err(|err| err.to_string())?,
+        Format::Toml => toml::from_str(&text).map_err(|err| err.to_string())?,
+      };
+      // keep timestamps as edited
+      save.write(&crypto, &save_dir, SaveFile::Slot(slot)).map_err(|err| err.to_string())?;
+      println!("imported {} into slot {}", path, slot);
+      Ok(())
+    }
+    _ => Err(USAGE.to_string()),
+  }
+}
+
+fn parse_slot(arg: Option<&str>) -> Result<u32, String> {
+  arg
+    .ok_or(USAGE)?
+    .parse()
+    .map_err(|_| format!("illegal slot: {}", arg.unwrap()))
+}
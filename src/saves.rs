@@ -1,17 +1,43 @@
+use crate::consts::*;
 use crate::crypto::Crypto;
+use crate::game::Mahou;
+use crate::game::Weapon;
+use crate::game::WorldSnapshot;
 use bevy::prelude::*;
-use bincode::deserialize;
-use home::home_dir;
 use serde::Deserialize;
 use serde::Serialize;
+use std::fmt;
+use std::fs::copy;
 use std::fs::create_dir_all;
 use std::fs::read;
+use std::fs::read_dir;
 use std::fs::remove_file;
-use std::fs::write;
+use std::fs::rename;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
 use std::time::SystemTime;
 
+mod migration;
+
+/// magic bytes of authenticated save files
+const SAVE_MAGIC: &[u8; 4] = b"UPSA";
+
+/// magic bytes of versioned but unauthenticated save files,
+/// encrypted with the legacy `CRYPTO_KEY`
+const LEGACY_SAVE_MAGIC: &[u8; 4] = b"UPSV";
+
+/// magic + version
+const SAVE_HEADER_LEN: usize = 8;
+/// header + digest + mac
+const SAVE_PAYLOAD_OFFSET: usize = SAVE_HEADER_LEN + 32 + 32;
+
+/// schema version of `GameSave`.
+/// bump it whenever the layout changes and add a migration in `saves/migration.rs`
+pub const SAVE_VERSION: u32 = 8;
+
 /// Game Data
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameSave {
@@ -23,8 +49,8 @@ pub struct GameSave {
   pub total_playing_time: Duration,
   /// save name (seems useless)
   pub saving_name: String,
-  /// unlocked hahou (?)
-  pub unlocked_mahou: String,
+  /// learnt spells, in the order they were learnt
+  pub unlocked_mahou: Vec<Mahou>,
   /// money
   pub money: u32,
   /// health
@@ -35,10 +61,224 @@ pub struct GameSave {
   pub energy: u32,
   /// max energy
   pub energy_limit: u32,
+  /// live entities, `None` before the first autosave
+  pub world: Option<WorldSnapshot>,
+  /// name of the area the player is in
+  pub area: String,
+  /// weapon the player fights with
+  pub weapon: Weapon,
+  /// monsters defeated so far, spells are learnt by it
+  pub defeated_monsters: u32,
+}
+
+/// Reasons why a save file can not be read
+#[derive(Debug)]
+pub enum SaveError {
+  /// file system errors
+  Io(std::io::Error),
+  /// unable to decrypt the payload
+  Decrypt,
+  /// payload decrypted but does not match the layout
+  Deserialize(bincode::Error),
+  /// written by a newer version of the game
+  UnknownVersion(u32),
+  /// digest mismatch, damaged on disk
+  Corrupted,
+  /// digest matches but the MAC does not,
+  /// edited outside the game or written by another installation
+  Tampered,
+}
+
+impl fmt::Display for SaveError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SaveError::Io(err) => write!(f, "io error: {}", err),
+      SaveError::Decrypt => write!(f, "unable to decrypt"),
+      SaveError::Deserialize(err) => write!(f, "broken data: {}", err),
+      SaveError::UnknownVersion(version) => write!(f, "unknown save version {}", version),
+      SaveError::Corrupted => write!(f, "corrupted"),
+      SaveError::Tampered => write!(f, "tampered or from another installation"),
+    }
+  }
+}
+
+impl From<std::io::Error> for SaveError {
+  fn from(err: std::io::Error) -> Self {
+    SaveError::Io(err)
+  }
+}
+
+impl From<bincode::Error> for SaveError {
+  fn from(err: bincode::Error) -> Self {
+    SaveError::Deserialize(err)
+  }
+}
+
+/// What happened while loading a save slot
+#[derive(Debug, Clone)]
+pub enum SlotStatus {
+  /// nothing saved in this slot
+  Empty,
+  /// loaded without problems
+  Loaded(GameSave),
+  /// the main file was unreadable and got quarantined,
+  /// restored from backup n (1 is the newest)
+  Recovered(GameSave, usize),
+  /// neither the main file nor any backup is readable,
+  /// all of them have been quarantined
+  Broken,
+  /// the main file, or a backup nothing newer could be read before,
+  /// does not match the installation key and is left untouched.
+  /// it may have been edited, or signed by another installation
+  Tampered,
+  /// written by a newer version of the game, left untouched
+  Unsupported(u32),
+}
+
+impl SlotStatus {
+  pub fn save(&self) -> Option<&GameSave> {
+    match self {
+      SlotStatus::Loaded(save) | SlotStatus::Recovered(save, _) => Some(save),
+      _ => None,
+    }
+  }
+}
+
+/// A file holding a save, with its own backups
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaveFile {
+  /// the save slot listed in the menu
+  Slot(u32),
+  /// the quick save of a slot, kept apart so it never takes a slot number
+  Quick(u32),
+}
+
+impl SaveFile {
+  fn file_name(&self) -> String {
+    match self {
+      SaveFile::Slot(slot) => format!("save{}.dat", slot),
+      SaveFile::Quick(slot) => format!("quick{}.dat", slot),
+    }
+  }
+}
+
+impl fmt::Display for SaveFile {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SaveFile::Slot(slot) => write!(f, "slot {}", slot),
+      SaveFile::Quick(slot) => write!(f, "quick save of slot {}", slot),
+    }
+  }
+}
+
+fn get_slot_path(save_dir: &Path, file: SaveFile) -> PathBuf {
+  save_dir.join(file.file_name())
+}
+
+/// backup 1 is the newest one
+fn get_backup_path(save_dir: &Path, file: SaveFile, index: usize) -> PathBuf {
+  save_dir.join(format!("{}.bak{}", file.file_name(), index))
+}
+
+/// write to a temporary file first and rename it over the target,
+/// so that a crash never leaves a half-written save behind
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+  let mut temp = path.as_os_str().to_owned();
+  temp.push(".tmp");
+  let temp = PathBuf::from(temp);
+  let mut file = File::create(&temp)?;
+  file.write_all(data)?;
+  file.sync_all()?;
+  rename(&temp, path)
+}
+
+/// move an unreadable file into `broken/` instead of deleting it,
+/// so that it can still be rescued by hand
+fn quarantine(save_dir: &Path, path: &Path) {
+  let quarantine_dir = save_dir.join(SAVE_QUARANTINE_DIR);
+  let timestamp = SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs();
+  let target = quarantine_dir.join(format!(
+    "{}.{}",
+    path.file_name().unwrap().to_string_lossy(),
+    timestamp
+  ));
+  match create_dir_all(&quarantine_dir).and_then(|_| rename(path, &target)) {
+    Ok(_) => warn!("quarantined {:?} to {:?}", path, target),
+    Err(err) => warn!("unable to quarantine {:?}: {}", path, err),
+  }
+}
+
+/// check that saves can be written into this directory
+pub fn check_save_dir(save_dir: &Path) -> Result<(), String> {
+  if !save_dir.is_dir() {
+    return Err(format!("{} is not a directory", save_dir.display()));
+  }
+  let probe = save_dir.join(".write_test");
+  write_atomic(&probe, &[])
+    .and_then(|_| remove_file(&probe))
+    .map_err(|err| format!("{} is not writable: {}", save_dir.display(), err))
+}
+
+/// all save files (including backups) inside the directory
+pub fn list_save_files(save_dir: &Path) -> Vec<PathBuf> {
+  let entries = match read_dir(save_dir) {
+    Ok(entries) => entries,
+    Err(_) => return Vec::new(),
+  };
+  entries
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+      let name = path.file_name().unwrap().to_string_lossy();
+      path.is_file() && (name.starts_with("save") || name.starts_with("quick")) && name.contains(".dat")
+    })
+    .collect()
+}
+
+/// slots having a save file or a backup, in ascending order.
+/// quick saves are not included.
+pub fn list_slots(save_dir: &Path) -> Vec<u32> {
+  let mut slots: Vec<u32> = list_save_files(save_dir)
+    .iter()
+    .filter_map(|path| {
+      let name = path.file_name()?.to_str()?;
+      name.strip_prefix("save")?.split(".dat").next()?.parse().ok()
+    })
+    .collect();
+  slots.sort();
+  slots.dedup();
+  slots
+}
+
+/// the slot after the last used one
+pub fn next_free_slot(save_dir: &Path) -> u32 {
+  list_slots(save_dir).last().map_or(0, |slot| slot + 1)
 }
 
-fn get_save_dir() -> Option<PathBuf> {
-  Some(home_dir()?.join(".kuzumajo").join("upstream"))
+/// move all save files into another directory.
+/// files already existing in the target are left untouched.
+/// returns how many files have been moved.
+pub fn move_saves(from: &Path, to: &Path) -> std::io::Result<usize> {
+  create_dir_all(to)?;
+  let mut moved = 0;
+  for path in list_save_files(from) {
+    let target = to.join(path.file_name().unwrap());
+    if target.exists() {
+      warn!("{:?} already exists, skipped", target);
+      continue;
+    }
+    // rename fails across devices, fall back to copy
+    if rename(&path, &target).is_err() {
+      copy(&path, &target)?;
+      remove_file(&path)?;
+    }
+    moved += 1;
+  }
+  info!("moved {} save files from {:?} to {:?}", moved, from, to);
+  Ok(moved)
 }
 
 impl GameSave {
@@ -48,45 +288,271 @@ impl GameSave {
       created_time: SystemTime::now(),
       total_playing_time: Duration::from_secs(0),
       saving_name: name,
-      unlocked_mahou: String::from("nashi"),
+      unlocked_mahou: Vec::new(),
       money: 0,
       health: 1500,
       health_limit: 2000,
       energy: 490,
       energy_limit: 500,
+      world: None,
+      area: START_AREA.to_string(),
+      weapon: Weapon::default(),
+      defeated_monsters: 0,
+    }
+  }
+
+  /// encode into the authenticated envelope
+  ///
+  /// ```text
+  /// | magic (4) | version (u32, le) | sha256 (32) | hmac-sha256 (32) | encrypted bincode payload |
+  /// ```
+  ///
+  /// both hashes cover the header and the payload
+  pub fn encode(&self, crypto: &Crypto) -> Vec<u8> {
+    let payload = crypto.encrypt(&bincode::serialize(self).unwrap());
+    let mut signed = Vec::with_capacity(SAVE_HEADER_LEN + payload.len());
+    signed.extend_from_slice(SAVE_MAGIC);
+    signed.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    signed.extend_from_slice(&payload);
+
+    let mut data = Vec::with_capacity(SAVE_PAYLOAD_OFFSET + payload.len());
+    data.extend_from_slice(&signed[..SAVE_HEADER_LEN]);
+    data.extend_from_slice(&Crypto::digest(&signed));
+    data.extend_from_slice(&crypto.sign(&signed));
+    data.extend_from_slice(&payload);
+    data
+  }
+
+  /// whether the data was written before per-installation keys
+  pub fn is_legacy(data: &[u8]) -> bool {
+    !data.starts_with(SAVE_MAGIC)
+  }
+
+  /// decode from any envelope, upgrading old layouts if needed.
+  /// legacy files without any magic header are treated as version 0.
+  pub fn decode(crypto: &Crypto, data: &[u8]) -> Result<Self, SaveError> {
+    let read_version = |data: &[u8]| {
+      let mut version = [0u8; 4];
+      version.copy_from_slice(&data[4..SAVE_HEADER_LEN]);
+      u32::from_le_bytes(version)
+    };
+
+    if data.starts_with(SAVE_MAGIC) {
+      if data.len() < SAVE_PAYLOAD_OFFSET {
+        return Err(SaveError::Corrupted);
+      }
+      let signed = [&data[..SAVE_HEADER_LEN], &data[SAVE_PAYLOAD_OFFSET..]].concat();
+      if Crypto::digest(&signed)[..] != data[SAVE_HEADER_LEN..SAVE_HEADER_LEN + 32] {
+        return Err(SaveError::Corrupted);
+      }
+      if !crypto.verify(&signed, &data[SAVE_HEADER_LEN + 32..SAVE_PAYLOAD_OFFSET]) {
+        return Err(SaveError::Tampered);
+      }
+      let version = read_version(data);
+      if version > SAVE_VERSION {
+        return Err(SaveError::UnknownVersion(version));
+      }
+      let payload = crypto
+        .decrypt(&data[SAVE_PAYLOAD_OFFSET..])
+        .map_err(|_| SaveError::Decrypt)?;
+      return migration::upgrade(version, &payload);
     }
+
+    let (version, payload) = if data.starts_with(LEGACY_SAVE_MAGIC) && data.len() >= SAVE_HEADER_LEN {
+      (read_version(data), &data[SAVE_HEADER_LEN..])
+    } else {
+      (0, data)
+    };
+    if version > SAVE_VERSION {
+      return Err(SaveError::UnknownVersion(version));
+    }
+    let payload = crypto.decrypt_legacy(payload).map_err(|_| SaveError::Decrypt)?;
+    migration::upgrade(version, &payload)
+  }
+
+  /// read the main file of a slot as is, without touching anything on disk
+  pub fn read(crypto: &Crypto, save_dir: &Path, file: SaveFile) -> Result<Self, SaveError> {
+    GameSave::decode(crypto, &read(get_slot_path(save_dir, file))?)
   }
 
-  pub fn load(crypto: &Crypto, slot: u8) -> Option<Self> {
-    let save_dir = get_save_dir().unwrap();
-    let save_path = save_dir.join(format!("save{}.dat", slot));
-    if let Ok(data) = read(&save_path) {
-      if let Ok(data) = crypto.decrypt(&data) {
-        if let Ok(save) = deserialize::<GameSave>(&data) {
-          return Some(save);
+  /// load a slot, falling back to the newest readable backup.
+  /// unreadable files are quarantined, never deleted.
+  /// tampered files are only reported, a wrong key would make all of them look tampered.
+  pub fn load(crypto: &Crypto, save_dir: &Path, file: SaveFile) -> SlotStatus {
+    let save_path = get_slot_path(save_dir, file);
+    let candidates = std::iter::once(save_path.clone())
+      .chain((1..=SAVE_BACKUP_COUNT).map(|index| get_backup_path(save_dir, file, index)));
+
+    let mut found = false;
+    let mut tampered = false;
+    for (index, path) in candidates.enumerate() {
+      let data = match read(&path) {
+        Ok(data) => data,
+        Err(_) => continue,
+      };
+      found = true;
+      match GameSave::decode(crypto, &data) {
+        Ok(save) if index == 0 => {
+          if GameSave::is_legacy(&data) {
+            // re-encrypt with the installation key, the legacy file stays as a backup
+            info!("migrating {} to the installation key", file);
+            if let Err(err) = save.write(crypto, save_dir, file) {
+              warn!("unable to migrate {}: {}", file, err);
+            }
+          }
+          return SlotStatus::Loaded(save);
+        }
+        Ok(save) => {
+          warn!("{} restored from backup {}", file, index);
+          // promote the backup, so the next load finds it directly
+          if let Err(err) = write_atomic(&save_path, &data) {
+            warn!("unable to restore {:?}: {}", save_path, err);
+          }
+          return SlotStatus::Recovered(save, index);
+        }
+        Err(SaveError::UnknownVersion(version)) => {
+          // keep it, a newer game may still read it
+          warn!("{:?} was written by a newer version ({})", path, version);
+          if index == 0 {
+            return SlotStatus::Unsupported(version);
+          }
+        }
+        Err(SaveError::Tampered) => {
+          warn!("{:?} tampered, left in place", path);
+          // recovering would overwrite it with a backup
+          if index == 0 {
+            return SlotStatus::Tampered;
+          }
+          tampered = true;
+        }
+        Err(err) => {
+          warn!("{:?} broken: {}", path, err);
+          quarantine(save_dir, &path);
         }
       }
-      warn!("save {} broken, trying to delete it", slot);
-      if remove_file(&save_path).is_ok() {
-        warn!("removed {:?}", save_path);
-      } else {
-        warn!("unable to remove {:?}", save_path);
+    }
+
+    if tampered {
+      SlotStatus::Tampered
+    } else if found {
+      SlotStatus::Broken
+    } else {
+      SlotStatus::Empty
+    }
+  }
+
+  /// remove a save file together with its backups
+  pub fn delete(save_dir: &Path, file: SaveFile) -> std::io::Result<()> {
+    let paths = std::iter::once(get_slot_path(save_dir, file))
+      .chain((1..=SAVE_BACKUP_COUNT).map(|index| get_backup_path(save_dir, file, index)));
+    for path in paths {
+      match remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+        _ => {}
       }
     }
-    return None;
+    info!("deleted {}", file);
+    Ok(())
+  }
+
+  /// write the slot atomically and rotate the previous ones into backups.
+  /// `last_modified_time` gets stamped on every write.
+  pub fn save(&mut self, crypto: &Crypto, save_dir: &Path, file: SaveFile) -> std::io::Result<()> {
+    self.last_modified_time = SystemTime::now();
+    self.write(crypto, save_dir, file)
   }
 
-  pub fn save(&self, crypto: &Crypto, slot: u8) -> std::io::Result<()> {
-    if slot >= 4 {
-      warn!("illegal slot: {}", slot);
+  /// same as `save`, but keeps `last_modified_time` as is
+  pub fn write(&self, crypto: &Crypto, save_dir: &Path, file: SaveFile) -> std::io::Result<()> {
+    create_dir_all(save_dir)?;
+    let save_path = get_slot_path(save_dir, file);
+
+    // the oldest backup gets overwritten by the second oldest
+    for index in (1..SAVE_BACKUP_COUNT).rev() {
+      let from = get_backup_path(save_dir, file, index);
+      if from.exists() {
+        rename(&from, get_backup_path(save_dir, file, index + 1))?;
+      }
+    }
+    // copy instead of rename, the main file stays valid until the new one is in place
+    if SAVE_BACKUP_COUNT > 0 && save_path.exists() {
+      copy(&save_path, get_backup_path(save_dir, file, 1))?;
     }
-    let save_dir = get_save_dir().unwrap();
-    create_dir_all(&save_dir)?;
-    let filename = save_dir.join(format!("save{}.dat", slot));
-    let data = bincode::serialize(self).unwrap();
-    let data = crypto.encrypt(&data);
-    write(filename, &data)?;
-    info!("game saved slot {}", slot);
+
+    write_atomic(&save_path, &self.encode(crypto))?;
+    info!("game saved {}", file);
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::game::{Facing, Health, MonsterSnapshot, PlayerSnapshot, PlayerState, Position, SoulPower, Velocity, WeaponSnapshot};
+
+  fn crypto() -> Crypto {
+    Crypto::new(b"test installation")
+  }
+
+  fn save() -> GameSave {
+    let mut save = GameSave::new("slot".to_string());
+    save.unlocked_mahou = vec![Mahou::Tama, Mahou::Kaen];
+    save.weapon = Weapon::DualBlade;
+    save.defeated_monsters = 7;
+    save.world = Some(WorldSnapshot {
+      player: Some(PlayerSnapshot {
+        position: Position(Vec2::new(1.0, 2.0)),
+        velocity: Velocity(Vec2::ZERO),
+        facing: Facing(Vec2::X),
+        health: Health { now: 1500, max: 2000 },
+        soul: SoulPower { now: 3, max: 10 },
+        state: PlayerState::Stand,
+        weapon: WeaponSnapshot::default(),
+        disable_wasd: false,
+        counter_attack: false,
+      }),
+      monsters: vec![MonsterSnapshot::with_default_defense(
+        Position(Vec2::new(10.0, 20.0)),
+        Velocity(Vec2::ZERO),
+        Health { now: 120, max: 500 },
+      )],
+      projectiles: Vec::new(),
+    });
+    save
+  }
+
+  /// re-sign the envelope with another version, as a newer game would write it
+  fn with_version(data: &[u8], version: u32, crypto: &Crypto) -> Vec<u8> {
+    let mut signed = data[..SAVE_HEADER_LEN].to_vec();
+    signed[4..].copy_from_slice(&version.to_le_bytes());
+    signed.extend_from_slice(&data[SAVE_PAYLOAD_OFFSET..]);
+
+    let mut data = signed[..SAVE_HEADER_LEN].to_vec();
+    data.extend_from_slice(&Crypto::digest(&signed));
+    data.extend_from_slice(&crypto.sign(&signed));
+    data.extend_from_slice(&signed[SAVE_HEADER_LEN..]);
+    data
+  }
+
+  #[test]
+  fn round_trip() {
+    let crypto = crypto();
+    let save = save();
+    let data = save.encode(&crypto);
+    assert!(data.starts_with(SAVE_MAGIC));
+    assert!(!GameSave::is_legacy(&data));
+
+    let decoded = GameSave::decode(&crypto, &data).unwrap();
+    assert_eq!(bincode::serialize(&decoded).unwrap(), bincode::serialize(&save).unwrap());
+  }
+
+  #[test]
+  fn unknown_version() {
+    let crypto = crypto();
+    let data = with_version(&save().encode(&crypto), SAVE_VERSION + 1, &crypto);
+    assert!(matches!(
+      GameSave::decode(&crypto, &data),
+      Err(SaveError::UnknownVersion(version)) if version == SAVE_VERSION + 1
+    ));
+  }
+}
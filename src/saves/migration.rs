@@ -0,0 +1,1035 @@
+//! Upgrade old save layouts to the current `GameSave`.
+//!
+//! Every layout that has ever been shipped keeps a frozen copy here,
+//! named after its schema version. Each version only knows how to
+//! convert from the one right before it, so adding a new field means:
+//!
+//! 1. copy the current `GameSave` here as `GameSaveV{SAVE_VERSION}`,
+//!    together with every snapshot type and enum it contains
+//! 2. bump `SAVE_VERSION` and change `GameSave`
+//! 3. convert the previous frozen layout into the new frozen one,
+//!    the new frozen one into `GameSave`, and extend `upgrade` below
+//!
+//! Only plain component values like `Position` or `Health` are shared with
+//! the game, everything else is decoded by its frozen copy, so reordering
+//! variants or adding fields in `snapshot.rs` never changes how old saves read.
+
+use super::GameSave;
+use super::SaveError;
+use super::SAVE_VERSION;
+use crate::consts::START_AREA;
+use crate::game::{AttackDamage, CollideRadius, ComboInput, ComboSnapshot, Element, Facing, Health, Mahou, MonsterSnapshot, OwnerSnapshot, PlayerSnapshot, PlayerState, Position, ProjectileSnapshot, SoulPower, SpriteSize, TimerSnapshot, Velocity, Weapon, WeaponSnapshot, WorldSnapshot};
+use bevy::math::Vec2;
+use bincode::deserialize;
+use serde::Deserialize;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// headerless saves written before versioning was introduced
+#[derive(Deserialize)]
+struct GameSaveV0 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+}
+
+/// first versioned layout, without the world snapshot
+#[derive(Deserialize)]
+struct GameSaveV1 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+}
+
+impl From<GameSaveV0> for GameSaveV1 {
+  fn from(save: GameSaveV0) -> Self {
+    GameSaveV1 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+    }
+  }
+}
+
+/// combo inputs, since the combo tree of version 4
+#[derive(Deserialize, Clone, Copy)]
+enum ComboInputV4 {
+  Attack1,
+  Attack2,
+}
+
+impl From<ComboInputV4> for ComboInput {
+  fn from(input: ComboInputV4) -> Self {
+    match input {
+      ComboInputV4::Attack1 => ComboInput::Attack1,
+      ComboInputV4::Attack2 => ComboInput::Attack2,
+    }
+  }
+}
+
+/// player states before weapon stances, named after the shield
+#[derive(Deserialize, Clone, Copy)]
+enum PlayerStateV2 {
+  ShieldAttackA,
+  ShieldAttackAA,
+  ShieldAttackAB,
+  ShieldAttackB,
+  ShieldAttackBB,
+  ShieldAttackBBB,
+
+  ShieldAssault,
+  ShieldAssaultA,
+  ShieldAssaultB,
+
+  Stand,
+}
+
+/// player states of any weapon since version 5,
+/// `Casting` was appended in version 6 but is never saved
+#[derive(Deserialize, Clone, Copy)]
+enum PlayerStateV5 {
+  Attack,
+  Assault,
+  AssaultAttack(ComboInputV4),
+  Stand,
+  Casting,
+}
+
+impl From<PlayerStateV2> for PlayerStateV5 {
+  fn from(state: PlayerStateV2) -> Self {
+    match state {
+      PlayerStateV2::ShieldAttackA
+      | PlayerStateV2::ShieldAttackAA
+      | PlayerStateV2::ShieldAttackAB
+      | PlayerStateV2::ShieldAttackB
+      | PlayerStateV2::ShieldAttackBB
+      | PlayerStateV2::ShieldAttackBBB => PlayerStateV5::Attack,
+      PlayerStateV2::ShieldAssault => PlayerStateV5::Assault,
+      PlayerStateV2::ShieldAssaultA => PlayerStateV5::AssaultAttack(ComboInputV4::Attack1),
+      PlayerStateV2::ShieldAssaultB => PlayerStateV5::AssaultAttack(ComboInputV4::Attack2),
+      PlayerStateV2::Stand => PlayerStateV5::Stand,
+    }
+  }
+}
+
+impl From<PlayerStateV5> for PlayerState {
+  fn from(state: PlayerStateV5) -> Self {
+    match state {
+      PlayerStateV5::Attack => PlayerState::Attack,
+      PlayerStateV5::Assault => PlayerState::Assault,
+      PlayerStateV5::AssaultAttack(input) => PlayerState::AssaultAttack(input.into()),
+      PlayerStateV5::Stand | PlayerStateV5::Casting => PlayerState::Stand,
+    }
+  }
+}
+
+/// combos of the shield before they were read from the combo tree
+#[derive(Deserialize, Clone, Copy)]
+enum ShieldAttackTypeV2 {
+  A,
+  AA,
+  AB,
+  B,
+  BB,
+  BBB,
+}
+
+impl ShieldAttackTypeV2 {
+  fn from_state(state: PlayerStateV2) -> Option<Self> {
+    match state {
+      PlayerStateV2::ShieldAttackA => Some(ShieldAttackTypeV2::A),
+      PlayerStateV2::ShieldAttackAA => Some(ShieldAttackTypeV2::AA),
+      PlayerStateV2::ShieldAttackAB => Some(ShieldAttackTypeV2::AB),
+      PlayerStateV2::ShieldAttackB => Some(ShieldAttackTypeV2::B),
+      PlayerStateV2::ShieldAttackBB => Some(ShieldAttackTypeV2::BB),
+      PlayerStateV2::ShieldAttackBBB => Some(ShieldAttackTypeV2::BBB),
+      _ => None,
+    }
+  }
+
+  /// path of the same combo in `assets/weapons/shield.ron`
+  fn path(self) -> Vec<ComboInputV4> {
+    use ComboInputV4::*;
+    match self {
+      ShieldAttackTypeV2::A => vec![Attack1],
+      ShieldAttackTypeV2::AA => vec![Attack1, Attack1],
+      ShieldAttackTypeV2::AB => vec![Attack1, Attack2],
+      ShieldAttackTypeV2::B => vec![Attack2],
+      ShieldAttackTypeV2::BB => vec![Attack2, Attack2],
+      ShieldAttackTypeV2::BBB => vec![Attack2, Attack2, Attack2],
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct TimerSnapshotV2 {
+  duration: f32,
+  elapsed: f32,
+  repeating: bool,
+}
+
+impl From<TimerSnapshotV2> for TimerSnapshot {
+  fn from(timer: TimerSnapshotV2) -> Self {
+    TimerSnapshot {
+      duration: timer.duration,
+      elapsed: timer.elapsed,
+      repeating: timer.repeating,
+    }
+  }
+}
+
+/// a combo timer with the path of its node, since version 4
+#[derive(Deserialize)]
+struct ComboSnapshotV4 {
+  timer: TimerSnapshotV2,
+  path: Vec<ComboInputV4>,
+}
+
+impl From<ComboSnapshotV4> for ComboSnapshot {
+  fn from(combo: ComboSnapshotV4) -> Self {
+    ComboSnapshot {
+      timer: combo.timer.into(),
+      path: combo.path.into_iter().map(ComboInput::from).collect(),
+    }
+  }
+}
+
+/// weapon timers of the player since version 4
+#[derive(Deserialize)]
+struct WeaponSnapshotV4 {
+  prefix: Option<ComboSnapshotV4>,
+  animation: Option<ComboSnapshotV4>,
+  assault: Option<TimerSnapshotV2>,
+  attack_cool_down: Option<TimerSnapshotV2>,
+  assault_cool_down: Option<TimerSnapshotV2>,
+}
+
+impl From<WeaponSnapshotV4> for WeaponSnapshot {
+  fn from(weapon: WeaponSnapshotV4) -> Self {
+    WeaponSnapshot {
+      prefix: weapon.prefix.map(ComboSnapshot::from),
+      animation: weapon.animation.map(ComboSnapshot::from),
+      assault: weapon.assault.map(TimerSnapshot::from),
+      attack_cool_down: weapon.attack_cool_down.map(TimerSnapshot::from),
+      assault_cool_down: weapon.assault_cool_down.map(TimerSnapshot::from),
+    }
+  }
+}
+
+/// elements of magical damage, since version 3
+#[derive(Deserialize, Clone, Copy)]
+enum ElementV3 {
+  Fire,
+  Water,
+  Wind,
+  Thunder,
+}
+
+impl From<ElementV3> for Element {
+  fn from(element: ElementV3) -> Self {
+    match element {
+      ElementV3::Fire => Element::Fire,
+      ElementV3::Water => Element::Water,
+      ElementV3::Wind => Element::Wind,
+      ElementV3::Thunder => Element::Thunder,
+    }
+  }
+}
+
+/// damage of projectiles, `Elemental` was appended in version 3
+#[derive(Deserialize, Clone, Copy)]
+enum AttackDamageV2 {
+  Physical { damage: u32, power: u32 },
+  Magical { damage: u32 },
+  Elemental { damage: u32, element: ElementV3 },
+}
+
+impl From<AttackDamageV2> for AttackDamage {
+  fn from(damage: AttackDamageV2) -> Self {
+    match damage {
+      AttackDamageV2::Physical { damage, power } => AttackDamage::Physical { damage, power },
+      AttackDamageV2::Magical { damage } => AttackDamage::Magical { damage },
+      AttackDamageV2::Elemental { damage, element } => AttackDamage::Elemental {
+        damage,
+        element: element.into(),
+      },
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum OwnerSnapshotV2 {
+  Player,
+  Monster(usize),
+}
+
+impl From<OwnerSnapshotV2> for OwnerSnapshot {
+  fn from(owner: OwnerSnapshotV2) -> Self {
+    match owner {
+      OwnerSnapshotV2::Player => OwnerSnapshot::Player,
+      OwnerSnapshotV2::Monster(index) => OwnerSnapshot::Monster(index),
+    }
+  }
+}
+
+/// projectiles, unchanged since version 2
+#[derive(Deserialize)]
+struct ProjectileSnapshotV2 {
+  position: Position,
+  velocity: Velocity,
+  radius: CollideRadius,
+  size: SpriteSize,
+  owner: Option<OwnerSnapshotV2>,
+  damage: Option<AttackDamageV2>,
+}
+
+impl From<ProjectileSnapshotV2> for ProjectileSnapshot {
+  fn from(projectile: ProjectileSnapshotV2) -> Self {
+    ProjectileSnapshot {
+      position: projectile.position,
+      velocity: projectile.velocity,
+      radius: projectile.radius,
+      size: projectile.size,
+      owner: projectile.owner.map(OwnerSnapshot::from),
+      damage: projectile.damage.map(AttackDamage::from),
+    }
+  }
+}
+
+/// shield of the world snapshot in versions 2 and 3
+#[derive(Deserialize)]
+struct ShieldSnapshotV2 {
+  prefix: Option<(TimerSnapshotV2, ShieldAttackTypeV2)>,
+  animation: Option<TimerSnapshotV2>,
+  assault: Option<TimerSnapshotV2>,
+  attack_cool_down: Option<TimerSnapshotV2>,
+  assault_cool_down: Option<TimerSnapshotV2>,
+}
+
+#[derive(Deserialize)]
+struct PlayerSnapshotV2 {
+  position: Position,
+  velocity: Velocity,
+  health: Health,
+  soul: SoulPower,
+  state: PlayerStateV2,
+  shield: ShieldSnapshotV2,
+  disable_wasd: bool,
+  counter_attack: bool,
+}
+
+/// monster of the world snapshot in versions 2 to 7, without defense
+#[derive(Deserialize)]
+struct MonsterSnapshotV2 {
+  position: Position,
+  velocity: Velocity,
+  health: Health,
+}
+
+impl From<MonsterSnapshotV2> for MonsterSnapshot {
+  fn from(monster: MonsterSnapshotV2) -> Self {
+    MonsterSnapshot::with_default_defense(monster.position, monster.velocity, monster.health)
+  }
+}
+
+/// world snapshot in versions 2 and 3
+#[derive(Deserialize)]
+struct WorldSnapshotV2 {
+  player: Option<PlayerSnapshotV2>,
+  monsters: Vec<MonsterSnapshotV2>,
+  projectiles: Vec<ProjectileSnapshotV2>,
+}
+
+/// player of the world snapshot in version 4, the shield was the only weapon
+#[derive(Deserialize)]
+struct PlayerSnapshotV4 {
+  position: Position,
+  velocity: Velocity,
+  health: Health,
+  soul: SoulPower,
+  state: PlayerStateV2,
+  shield: WeaponSnapshotV4,
+  disable_wasd: bool,
+  counter_attack: bool,
+}
+
+#[derive(Deserialize)]
+struct WorldSnapshotV4 {
+  player: Option<PlayerSnapshotV4>,
+  monsters: Vec<MonsterSnapshotV2>,
+  projectiles: Vec<ProjectileSnapshotV2>,
+}
+
+/// player of the world snapshot in versions 5 and 6, without the facing
+#[derive(Deserialize)]
+struct PlayerSnapshotV5 {
+  position: Position,
+  velocity: Velocity,
+  health: Health,
+  soul: SoulPower,
+  state: PlayerStateV5,
+  weapon: WeaponSnapshotV4,
+  disable_wasd: bool,
+  counter_attack: bool,
+}
+
+#[derive(Deserialize)]
+struct WorldSnapshotV5 {
+  player: Option<PlayerSnapshotV5>,
+  monsters: Vec<MonsterSnapshotV2>,
+  projectiles: Vec<ProjectileSnapshotV2>,
+}
+
+impl From<WorldSnapshotV2> for WorldSnapshotV4 {
+  fn from(world: WorldSnapshotV2) -> Self {
+    WorldSnapshotV4 {
+      player: world.player.map(|player| {
+        let state = player.state;
+        let shield = player.shield;
+        PlayerSnapshotV4 {
+          position: player.position,
+          velocity: player.velocity,
+          health: player.health,
+          soul: player.soul,
+          state: player.state,
+          shield: WeaponSnapshotV4 {
+            prefix: shield.prefix.map(|(timer, attack_type)| ComboSnapshotV4 { timer, path: attack_type.path() }),
+            // the combo of an animation was told by the player state
+            animation: shield.animation.and_then(|timer| {
+              ShieldAttackTypeV2::from_state(state).map(|attack_type| ComboSnapshotV4 { timer, path: attack_type.path() })
+            }),
+            assault: shield.assault,
+            attack_cool_down: shield.attack_cool_down,
+            assault_cool_down: shield.assault_cool_down,
+          },
+          disable_wasd: player.disable_wasd,
+          counter_attack: player.counter_attack,
+        }
+      }),
+      monsters: world.monsters,
+      projectiles: world.projectiles,
+    }
+  }
+}
+
+impl From<WorldSnapshotV4> for WorldSnapshotV5 {
+  fn from(world: WorldSnapshotV4) -> Self {
+    WorldSnapshotV5 {
+      player: world.player.map(|player| PlayerSnapshotV5 {
+        position: player.position,
+        velocity: player.velocity,
+        health: player.health,
+        soul: player.soul,
+        state: player.state.into(),
+        weapon: player.shield,
+        disable_wasd: player.disable_wasd,
+        counter_attack: player.counter_attack,
+      }),
+      monsters: world.monsters,
+      projectiles: world.projectiles,
+    }
+  }
+}
+
+/// player of the world snapshot in version 7, with the facing
+#[derive(Deserialize)]
+struct PlayerSnapshotV7 {
+  position: Position,
+  velocity: Velocity,
+  facing: Facing,
+  health: Health,
+  soul: SoulPower,
+  state: PlayerStateV5,
+  weapon: WeaponSnapshotV4,
+  disable_wasd: bool,
+  counter_attack: bool,
+}
+
+impl From<PlayerSnapshotV7> for PlayerSnapshot {
+  fn from(player: PlayerSnapshotV7) -> Self {
+    PlayerSnapshot {
+      position: player.position,
+      velocity: player.velocity,
+      facing: player.facing,
+      health: player.health,
+      soul: player.soul,
+      state: player.state.into(),
+      weapon: player.weapon.into(),
+      disable_wasd: player.disable_wasd,
+      counter_attack: player.counter_attack,
+    }
+  }
+}
+
+/// world snapshot in version 7
+#[derive(Deserialize)]
+struct WorldSnapshotV7 {
+  player: Option<PlayerSnapshotV7>,
+  monsters: Vec<MonsterSnapshotV2>,
+  projectiles: Vec<ProjectileSnapshotV2>,
+}
+
+impl From<WorldSnapshotV5> for WorldSnapshotV7 {
+  fn from(world: WorldSnapshotV5) -> Self {
+    WorldSnapshotV7 {
+      player: world.player.map(|player| PlayerSnapshotV7 {
+        position: player.position,
+        velocity: player.velocity,
+        // players kept facing where they last moved, to the right before moving at all
+        facing: Facing(match player.velocity.0.normalize_or_zero() {
+          direction if direction == Vec2::ZERO => Vec2::X,
+          direction => direction,
+        }),
+        health: player.health,
+        soul: player.soul,
+        state: player.state,
+        weapon: player.weapon,
+        disable_wasd: player.disable_wasd,
+        counter_attack: player.counter_attack,
+      }),
+      monsters: world.monsters,
+      projectiles: world.projectiles,
+    }
+  }
+}
+
+impl From<WorldSnapshotV7> for WorldSnapshot {
+  fn from(world: WorldSnapshotV7) -> Self {
+    WorldSnapshot {
+      player: world.player.map(PlayerSnapshot::from),
+      monsters: world.monsters.into_iter().map(MonsterSnapshot::from).collect(),
+      projectiles: world.projectiles.into_iter().map(ProjectileSnapshot::from).collect(),
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum WeaponV5 {
+  Shield,
+  DualBlade,
+}
+
+impl From<WeaponV5> for Weapon {
+  fn from(weapon: WeaponV5) -> Self {
+    match weapon {
+      WeaponV5::Shield => Weapon::Shield,
+      WeaponV5::DualBlade => Weapon::DualBlade,
+    }
+  }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum MahouV6 {
+  Tama,
+  Kaen,
+  Ikazuchi,
+}
+
+impl From<MahouV6> for Mahou {
+  fn from(mahou: MahouV6) -> Self {
+    match mahou {
+      MahouV6::Tama => Mahou::Tama,
+      MahouV6::Kaen => Mahou::Kaen,
+      MahouV6::Ikazuchi => Mahou::Ikazuchi,
+    }
+  }
+}
+
+/// with the world snapshot, but without the area
+#[derive(Deserialize)]
+struct GameSaveV2 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV2>,
+}
+
+impl From<GameSaveV1> for GameSaveV2 {
+  fn from(save: GameSaveV1) -> Self {
+    GameSaveV2 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: None,
+    }
+  }
+}
+
+/// with the area, shield combos were hard coded
+#[derive(Deserialize)]
+struct GameSaveV3 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV2>,
+  area: String,
+}
+
+impl From<GameSaveV2> for GameSaveV3 {
+  fn from(save: GameSaveV2) -> Self {
+    GameSaveV3 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world,
+      area: START_AREA.to_string(),
+    }
+  }
+}
+
+/// with combos read from the combo tree, the shield was the only weapon
+#[derive(Deserialize)]
+struct GameSaveV4 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV4>,
+  area: String,
+}
+
+impl From<GameSaveV3> for GameSaveV4 {
+  fn from(save: GameSaveV3) -> Self {
+    GameSaveV4 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world.map(WorldSnapshotV4::from),
+      area: save.area,
+    }
+  }
+}
+
+/// with the weapon, spells were a placeholder string
+#[derive(Deserialize)]
+struct GameSaveV5 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: String,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV5>,
+  area: String,
+  weapon: WeaponV5,
+}
+
+impl From<GameSaveV4> for GameSaveV5 {
+  fn from(save: GameSaveV4) -> Self {
+    GameSaveV5 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world.map(WorldSnapshotV5::from),
+      area: save.area,
+      weapon: WeaponV5::Shield,
+    }
+  }
+}
+
+/// with learnt spells, the facing of the player was not kept
+#[derive(Deserialize)]
+struct GameSaveV6 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: Vec<MahouV6>,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV5>,
+  area: String,
+  weapon: WeaponV5,
+  defeated_monsters: u32,
+}
+
+impl From<GameSaveV5> for GameSaveV6 {
+  fn from(save: GameSaveV5) -> Self {
+    GameSaveV6 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      // no spell has ever been learnt, the starting ones are learnt again in game
+      unlocked_mahou: Vec::new(),
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world,
+      area: save.area,
+      weapon: save.weapon,
+      defeated_monsters: 0,
+    }
+  }
+}
+
+/// with the facing of the player, monsters did not keep their defense
+#[derive(Deserialize)]
+struct GameSaveV7 {
+  last_modified_time: SystemTime,
+  created_time: SystemTime,
+  total_playing_time: Duration,
+  saving_name: String,
+  unlocked_mahou: Vec<MahouV6>,
+  money: u32,
+  health: u32,
+  health_limit: u32,
+  energy: u32,
+  energy_limit: u32,
+  world: Option<WorldSnapshotV7>,
+  area: String,
+  weapon: WeaponV5,
+  defeated_monsters: u32,
+}
+
+impl From<GameSaveV6> for GameSaveV7 {
+  fn from(save: GameSaveV6) -> Self {
+    GameSaveV7 {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou,
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world.map(WorldSnapshotV7::from),
+      area: save.area,
+      weapon: save.weapon,
+      defeated_monsters: save.defeated_monsters,
+    }
+  }
+}
+
+impl From<GameSaveV7> for GameSave {
+  fn from(save: GameSaveV7) -> Self {
+    GameSave {
+      last_modified_time: save.last_modified_time,
+      created_time: save.created_time,
+      total_playing_time: save.total_playing_time,
+      saving_name: save.saving_name,
+      unlocked_mahou: save.unlocked_mahou.into_iter().map(Mahou::from).collect(),
+      money: save.money,
+      health: save.health,
+      health_limit: save.health_limit,
+      energy: save.energy,
+      energy_limit: save.energy_limit,
+      world: save.world.map(WorldSnapshot::from),
+      area: save.area,
+      weapon: save.weapon.into(),
+      defeated_monsters: save.defeated_monsters,
+    }
+  }
+}
+
+fn upgrade_v1(version: u32, data: &[u8]) -> Result<GameSaveV1, SaveError> {
+  match version {
+    0 => Ok(deserialize::<GameSaveV0>(data)?.into()),
+    _ => Ok(deserialize::<GameSaveV1>(data)?),
+  }
+}
+
+fn upgrade_v2(version: u32, data: &[u8]) -> Result<GameSaveV2, SaveError> {
+  match version {
+    0 | 1 => Ok(upgrade_v1(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV2>(data)?),
+  }
+}
+
+fn upgrade_v3(version: u32, data: &[u8]) -> Result<GameSaveV3, SaveError> {
+  match version {
+    0 | 1 | 2 => Ok(upgrade_v2(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV3>(data)?),
+  }
+}
+
+fn upgrade_v4(version: u32, data: &[u8]) -> Result<GameSaveV4, SaveError> {
+  match version {
+    0 | 1 | 2 | 3 => Ok(upgrade_v3(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV4>(data)?),
+  }
+}
+
+fn upgrade_v5(version: u32, data: &[u8]) -> Result<GameSaveV5, SaveError> {
+  match version {
+    0 | 1 | 2 | 3 | 4 => Ok(upgrade_v4(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV5>(data)?),
+  }
+}
+
+fn upgrade_v6(version: u32, data: &[u8]) -> Result<GameSaveV6, SaveError> {
+  match version {
+    0 | 1 | 2 | 3 | 4 | 5 => Ok(upgrade_v5(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV6>(data)?),
+  }
+}
+
+fn upgrade_v7(version: u32, data: &[u8]) -> Result<GameSaveV7, SaveError> {
+  match version {
+    0 | 1 | 2 | 3 | 4 | 5 | 6 => Ok(upgrade_v6(version, data)?.into()),
+    _ => Ok(deserialize::<GameSaveV7>(data)?),
+  }
+}
+
+/// decode a decrypted payload written with `version` into the current layout
+pub(super) fn upgrade(version: u32, data: &[u8]) -> Result<GameSave, SaveError> {
+  match version {
+    0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 => Ok(upgrade_v7(version, data)?.into()),
+    SAVE_VERSION => Ok(deserialize::<GameSave>(data)?),
+    _ => Err(SaveError::UnknownVersion(version)),
+  }
+}
+
+/// Fixtures are plain tuples in the order the fields were declared, enum variants
+/// by their index, so they stay the bytes older games wrote whatever happens to the types.
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bincode::serialize;
+  use serde::Serialize;
+
+  fn time() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000)
+  }
+
+  /// fields every version starts with, `unlocked_mahou` changed its type in version 6
+  fn header<M: Serialize>(unlocked_mahou: M) -> (SystemTime, SystemTime, Duration, &'static str, M, u32, u32, u32, u32, u32) {
+    (time(), time(), Duration::from_secs(3600), "slot", unlocked_mahou, 100, 1500, 2000, 490, 500)
+  }
+
+  fn timer() -> (f32, f32, bool) {
+    (0.5, 0.25, false)
+  }
+
+  fn monster() -> (Position, Velocity, Health) {
+    (Position(Vec2::new(10.0, 20.0)), Velocity(Vec2::ZERO), Health { now: 120, max: 500 })
+  }
+
+  /// owned by monster 0, elemental damage of thunder
+  fn projectile() -> (Position, Velocity, CollideRadius, SpriteSize, Option<(u32, u64)>, Option<(u32, u32, u32)>) {
+    (
+      Position(Vec2::new(-5.0, 0.0)),
+      Velocity(Vec2::new(3.0, 0.0)),
+      CollideRadius(8.0),
+      SpriteSize(Vec2::splat(16.0)),
+      Some((1, 0)),
+      Some((2, 30, 3)),
+    )
+  }
+
+  /// moving up without the facing
+  fn player_body() -> (Position, Velocity) {
+    (Position(Vec2::new(1.0, 2.0)), Velocity(Vec2::new(0.0, 2.0)))
+  }
+
+  fn player_stats() -> (Health, SoulPower) {
+    (Health { now: 1500, max: 2000 }, SoulPower { now: 3, max: 10 })
+  }
+
+  /// `WeaponSnapshotV4`, in the middle of the combo `Attack1, Attack2`
+  fn weapon() -> impl Serialize {
+    let combo = Some((timer(), vec![0u32, 1u32]));
+    (combo.clone(), combo, None::<(f32, f32, bool)>, Some(timer()), None::<(f32, f32, bool)>)
+  }
+
+  /// shield attack `AB` in versions 2 and 3
+  fn world_v2() -> impl Serialize {
+    let shield = (Some((timer(), 2u32)), Some(timer()), None::<(f32, f32, bool)>, Some(timer()), None::<(f32, f32, bool)>);
+    let player = (player_body(), player_stats(), 2u32, shield, false, true);
+    (Some(player), vec![monster()], vec![projectile()])
+  }
+
+  /// shield attack `AB` read from the combo tree
+  fn world_v4() -> impl Serialize {
+    let player = (player_body(), player_stats(), 2u32, weapon(), false, true);
+    (Some(player), vec![monster()], vec![projectile()])
+  }
+
+  /// attacking with any weapon
+  fn world_v5() -> impl Serialize {
+    let player = (player_body(), player_stats(), 0u32, weapon(), false, true);
+    (Some(player), vec![monster()], vec![projectile()])
+  }
+
+  /// facing left while moving up
+  fn world_v7() -> impl Serialize {
+    let (position, velocity) = player_body();
+    let player = (position, velocity, Facing(Vec2::new(-1.0, 0.0)), player_stats(), 0u32, weapon(), false, true);
+    (Some(player), vec![monster()], vec![projectile()])
+  }
+
+  fn fixture(version: u32) -> Vec<u8> {
+    let result = match version {
+      0 | 1 => serialize(&header("")),
+      2 => serialize(&(header(""), Some(world_v2()))),
+      3 => serialize(&(header(""), Some(world_v2()), "forest")),
+      4 => serialize(&(header(""), Some(world_v4()), "forest")),
+      5 => serialize(&(header(""), Some(world_v5()), "forest", 1u32)),
+      6 => serialize(&(header(vec![1u32]), Some(world_v5()), "forest", 1u32, 7u32)),
+      7 => serialize(&(header(vec![1u32]), Some(world_v7()), "forest", 1u32, 7u32)),
+      _ => unreachable!(),
+    };
+    result.unwrap()
+  }
+
+  #[test]
+  fn upgrade_every_past_version() {
+    for version in 0..SAVE_VERSION {
+      let save = upgrade(version, &fixture(version)).unwrap_or_else(|err| panic!("version {}: {}", version, err));
+
+      assert_eq!(save.created_time, time(), "version {}", version);
+      assert_eq!(save.total_playing_time, Duration::from_secs(3600));
+      assert_eq!(save.saving_name, "slot");
+      assert_eq!((save.money, save.health, save.health_limit), (100, 1500, 2000));
+      assert_eq!((save.energy, save.energy_limit), (490, 500));
+
+      let area = if version < 3 { START_AREA } else { "forest" };
+      assert_eq!(save.area, area, "version {}", version);
+      let weapon = if version < 5 { Weapon::Shield } else { Weapon::DualBlade };
+      assert_eq!(save.weapon, weapon, "version {}", version);
+      let (unlocked_mahou, defeated_monsters) = if version < 6 { (vec![], 0) } else { (vec![Mahou::Kaen], 7) };
+      assert_eq!(save.unlocked_mahou, unlocked_mahou, "version {}", version);
+      assert_eq!(save.defeated_monsters, defeated_monsters, "version {}", version);
+
+      let world = match save.world {
+        Some(world) => world,
+        None => {
+          assert!(version < 2, "version {} lost its world", version);
+          continue;
+        }
+      };
+      assert!(version >= 2, "version {} can not have a world", version);
+
+      let player = world.player.unwrap();
+      assert_eq!(player.position.0, Vec2::new(1.0, 2.0));
+      assert_eq!(player.health.now, 1500);
+      assert_eq!(player.soul.now, 3);
+      assert_eq!(player.state, PlayerState::Attack, "version {}", version);
+      let facing = if version < 7 { Vec2::Y } else { -Vec2::X };
+      assert_eq!(player.facing.0, facing, "version {}", version);
+      let prefix = player.weapon.prefix.unwrap();
+      assert_eq!(prefix.timer.elapsed, 0.25);
+      assert_eq!(prefix.path, vec![ComboInput::Attack1, ComboInput::Attack2], "version {}", version);
+      let animation = player.weapon.animation.unwrap();
+      assert_eq!(animation.path, vec![ComboInput::Attack1, ComboInput::Attack2], "version {}", version);
+      assert!(player.weapon.assault.is_none());
+      assert!(player.weapon.attack_cool_down.is_some());
+      assert!(!player.disable_wasd && player.counter_attack);
+
+      assert_eq!(world.monsters.len(), 1);
+      assert_eq!(world.monsters[0].position.0, Vec2::new(10.0, 20.0));
+      assert_eq!(world.monsters[0].health.now, 120);
+
+      assert_eq!(world.projectiles.len(), 1);
+      let projectile = &world.projectiles[0];
+      assert_eq!(projectile.radius.0, 8.0);
+      assert_eq!(projectile.owner, Some(OwnerSnapshot::Monster(0)));
+      assert!(
+        matches!(projectile.damage, Some(AttackDamage::Elemental { damage: 30, element: Element::Thunder })),
+        "version {}",
+        version
+      );
+    }
+  }
+
+  #[test]
+  fn upgrade_unknown_version() {
+    let data = fixture(7);
+    assert!(matches!(upgrade(SAVE_VERSION + 1, &data), Err(SaveError::UnknownVersion(version)) if version == SAVE_VERSION + 1));
+  }
+
+  #[test]
+  fn combo_snapshot_is_laid_out_like_a_tuple() {
+    let timer = TimerSnapshot {
+      duration: 0.5,
+      elapsed: 0.25,
+      repeating: false,
+    };
+    let path = vec![ComboInput::Attack2, ComboInput::Attack1];
+    let combo = ComboSnapshot {
+      timer,
+      path: path.clone(),
+    };
+    assert_eq!(serialize(&combo).unwrap(), serialize(&(timer, path)).unwrap());
+  }
+}
@@ -1,10 +1,21 @@
+use crate::config::DisplaySettings;
 use crate::config::GameConfig;
+use crate::display::refresh_monitors;
+use crate::display::Monitors;
 use crate::consts::*;
+use crate::input::InputAction;
+use crate::input::InputBinding;
+use crate::saves::check_save_dir;
+use crate::saves::list_save_files;
+use crate::saves::move_saves;
+use crate::sounds::AudioMixer;
 use crate::text_input::TextInputText;
 use crate::FontAssets;
 use crate::MousePosition;
+use crate::WindowSize;
 use bevy::prelude::*;
 use bevy::ui::FocusPolicy;
+use std::path::PathBuf;
 
 /// Used to indentify entities in this page
 struct SettingsUI;
@@ -30,6 +41,7 @@ struct SettingsMaterials {
   slide_bar: Handle<ColorMaterial>,
 
   nav_bar_background: Handle<ColorMaterial>,
+  dialog_background: Handle<ColorMaterial>,
 
   transparent: Handle<ColorMaterial>,
 }
@@ -52,6 +64,64 @@ struct SettingSelectButton;
 /// Used to track dragging of the slider
 struct SettingDraggingSlide(Entity);
 
+/// Used to indentify key binding button entities in this page
+struct SettingBindingButton;
+
+/// Used to indentify the button resetting all key bindings
+struct ResetBindingsButton;
+
+/// Waiting for a key or mouse button to bind to this binding button
+struct CapturingBinding(Entity);
+
+/// Pages of the settings, switched by the navigation bar
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsPage {
+  General,
+  Display,
+  Controls,
+}
+
+/// Used to indentify the message text in the navigation bar
+struct SettingsMessageUI;
+
+/// Used to indentify the "move saves" dialog
+struct MoveSavesDialogUI;
+
+/// Buttons of the "move saves" dialog
+enum MoveSavesButton {
+  /// Move saves into the new directory
+  Move,
+  /// Leave saves in the old directory
+  Keep,
+}
+
+/// Save directory has been changed while the old one still contains saves
+struct PendingSaveMove {
+  from: PathBuf,
+  to: PathBuf,
+}
+
+/// Used to indentify the "keep display settings" dialog
+struct KeepDisplayDialogUI;
+
+/// Used to indentify the countdown text of the "keep display settings" dialog
+struct KeepDisplayCountdownUI;
+
+/// Buttons of the "keep display settings" dialog
+enum KeepDisplayButton {
+  /// Keep the new display settings
+  Keep,
+  /// Go back to the previous display settings
+  Revert,
+}
+
+/// New display settings are applied but not confirmed yet
+struct PendingDisplayRevert {
+  /// the last confirmed display settings
+  previous: DisplaySettings,
+  timer: Timer,
+}
+
 /// Initialize materials
 impl FromWorld for SettingsMaterials {
   fn from_world(world: &mut World) -> Self {
@@ -78,6 +148,7 @@ impl FromWorld for SettingsMaterials {
       slide_bar: materials.add(asset_server.load("images/ui/slider-bar.png").into()),
 
       nav_bar_background: materials.add(Color::rgb(0.95, 0.95, 0.95).into()),
+      dialog_background: materials.add(Color::rgba(0.9, 0.9, 0.9, 0.95).into()),
 
       transparent: materials.add(Color::NONE.into()),
     }
@@ -95,6 +166,8 @@ pub enum SettingType {
   Slide(f32),
   /// A select button which has a list of options and a selected option
   Select(usize, Vec<String>),
+  /// A key or mouse button bound to an action
+  Binding(InputBinding),
 }
 
 #[derive(Clone)]
@@ -106,11 +179,30 @@ pub enum SettingItem {
   VolumeVoice,
   SaveDir,
   Fullscreen,
+  Borderless,
+  Monitor,
   Resolution,
   Decorations,
+  VSync,
+  FpsLimit,
+  UiScale,
   AttackToMouse,
   AssaultToMouse,
   MouseSensitivity,
+  GamepadDeadZone,
+  GamepadSensitivity,
+  KeyBinding(InputAction),
+}
+
+impl SettingItem {
+  /// items applied to the window
+  fn is_display(&self) -> bool {
+    use SettingItem::*;
+    matches!(
+      self,
+      Fullscreen | Borderless | Monitor | Resolution | Decorations | VSync | UiScale
+    )
+  }
 }
 
 /// Navigation buttons
@@ -121,6 +213,8 @@ enum NavButton {
   Apply,
   /// Reset changes
   Reset,
+  /// Switch to another settings page
+  Page(SettingsPage),
 }
 
 /// Setup settings page
@@ -129,6 +223,7 @@ fn setup_settings(
   materials: Res<SettingsMaterials>,
   font_assets: Res<FontAssets>,
   config: Res<GameConfig>,
+  monitors: Res<Monitors>,
 ) {
   commands
     .spawn_bundle(NodeBundle {
@@ -223,240 +318,471 @@ fn setup_settings(
             })
             .insert(NavButton::Reset)
             .insert(NavButtonUI);
+
+          // page buttons
+          for (page, title) in vec![
+            (SettingsPage::General, "一般"),
+            (SettingsPage::Display, "画面"),
+            (SettingsPage::Controls, "操作"),
+          ] {
+            parent
+              .spawn_bundle(ButtonBundle {
+                style: button_style.clone(),
+                material: materials.button_normal.clone(),
+                ..Default::default()
+              })
+              .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                  text: Text::with_section(title, text_style.clone(), text_alignment.clone()),
+                  ..Default::default()
+                });
+              })
+              .insert(NavButton::Page(page))
+              .insert(NavButtonUI);
+          }
+
+          // message
+          parent
+            .spawn_bundle(TextBundle {
+              style: Style {
+                max_size: Size::new(Val::Px(200.0), Val::Undefined),
+                margin: Rect {
+                  top: Val::Px(20.0),
+                  ..Default::default()
+                },
+                ..Default::default()
+              },
+              text: Text::with_section(
+                "",
+                TextStyle {
+                  font: font_assets.default_font.clone(),
+                  font_size: 20.0,
+                  color: Color::rgb(0.8, 0.2, 0.2),
+                },
+                Default::default(),
+              ),
+              ..Default::default()
+            })
+            .insert(SettingsMessageUI);
         });
 
-      // right <div>
+      // general <div>
+      spawn_settings_page(
+        parent,
+        SettingsPage::General,
+        vec![
+          ("ボリューム", SettingItem::Volume),
+          ("音楽", SettingItem::VolumeMusic),
+          ("効果音", SettingItem::VolumeSfx),
+          ("声", SettingItem::VolumeVoice),
+          ("保存先", SettingItem::SaveDir),
+          ("マウスの方向に攻撃", SettingItem::AttackToMouse),
+          ("マウスの方向に突撃", SettingItem::AssaultToMouse),
+          ("マウス感度", SettingItem::MouseSensitivity),
+          ("スティックの遊び", SettingItem::GamepadDeadZone),
+          ("スティック感度", SettingItem::GamepadSensitivity),
+        ],
+        &materials,
+        &font_assets,
+        &config,
+        &monitors,
+      );
+
+      // display <div>
+      spawn_settings_page(
+        parent,
+        SettingsPage::Display,
+        vec![
+          ("フルスクリーン", SettingItem::Fullscreen),
+          ("ボーダーレス", SettingItem::Borderless),
+          ("モニター", SettingItem::Monitor),
+          ("解像度", SettingItem::Resolution),
+          ("ウィンドウ装飾", SettingItem::Decorations),
+          ("垂直同期", SettingItem::VSync),
+          ("フレームレート上限", SettingItem::FpsLimit),
+          ("UIの拡大率", SettingItem::UiScale),
+        ],
+        &materials,
+        &font_assets,
+        &config,
+        &monitors,
+      );
+
+      // controls <div>
       parent
         .spawn_bundle(NodeBundle {
           style: Style {
+            display: Display::None,
             size: Size::new(Val::Undefined, Val::Percent(100.0)),
             margin: Rect::all(Val::Auto),
-            flex_direction: FlexDirection::Row,
-            justify_content: JustifyContent::SpaceBetween,
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
             ..Default::default()
           },
           material: materials.transparent.clone(),
           ..Default::default()
         })
+        .insert(SettingsPage::Controls)
         .with_children(|parent| {
-          use SettingItem::*;
-          use SettingType::*;
-          let setting_list = [
-            ("ボリューム", Volume),
-            ("音楽", VolumeMusic),
-            ("効果音", VolumeSfx),
-            ("声", VolumeVoice),
-            ("保存先", SaveDir),
-            ("フルスクリーン", Fullscreen),
-            ("解像度", Resolution),
-            ("ウィンドウ装飾", Decorations),
-            ("マウスの方向に攻撃", AttackToMouse),
-            ("マウスの方向に突撃", AssaultToMouse),
-            ("マウス感度", MouseSensitivity),
-          ];
-
-          // left name
+          let text_style = TextStyle {
+            font: font_assets.default_font.clone(),
+            font_size: 24.0,
+            color: Color::BLACK,
+          };
+
           parent
             .spawn_bundle(NodeBundle {
-              style: Style {
-                size: Size::new(Val::Px(200.0), Val::Percent(100.0)),
-                flex_direction: FlexDirection::ColumnReverse,
-                align_items: AlignItems::FlexEnd,
-                justify_content: JustifyContent::Center,
-                ..Default::default()
-              },
               material: materials.transparent.clone(),
               ..Default::default()
             })
             .with_children(|parent| {
-              for (name, _) in setting_list.iter() {
+              // two columns of actions
+              for actions in InputAction::ALL.chunks((InputAction::ALL.len() + 1) / 2) {
                 parent
                   .spawn_bundle(NodeBundle {
                     style: Style {
-                      size: Size::new(Val::Auto, Val::Px(50.0)),
+                      flex_direction: FlexDirection::ColumnReverse,
                       margin: Rect {
+                        left: Val::Px(20.0),
                         right: Val::Px(20.0),
                         ..Default::default()
                       },
-                      align_items: AlignItems::Center,
-                      justify_content: JustifyContent::Center,
                       ..Default::default()
                     },
                     material: materials.transparent.clone(),
                     ..Default::default()
                   })
                   .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                      text: Text::with_section(
-                        *name,
-                        TextStyle {
-                          font: font_assets.default_font.clone(),
-                          font_size: 32.0,
-                          color: Color::BLACK,
-                        },
-                        Default::default(),
-                      ),
-                      ..Default::default()
-                    });
+                    for action in actions.iter() {
+                      let item = SettingItem::KeyBinding(*action);
+                      let st = config.get_settings_type(&item, &monitors);
+                      let binding = match &st {
+                        SettingType::Binding(binding) => binding.title(),
+                        _ => String::new(),
+                      };
+
+                      parent
+                        .spawn_bundle(NodeBundle {
+                          style: Style {
+                            size: Size::new(Val::Auto, Val::Px(50.0)),
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                          },
+                          material: materials.transparent.clone(),
+                          ..Default::default()
+                        })
+                        .with_children(|parent| {
+                          // action name
+                          parent
+                            .spawn_bundle(NodeBundle {
+                              style: Style {
+                                size: Size::new(Val::Px(180.0), Val::Auto),
+                                margin: Rect {
+                                  right: Val::Px(20.0),
+                                  ..Default::default()
+                                },
+                                justify_content: JustifyContent::FlexEnd,
+                                ..Default::default()
+                              },
+                              material: materials.transparent.clone(),
+                              ..Default::default()
+                            })
+                            .with_children(|parent| {
+                              parent.spawn_bundle(TextBundle {
+                                text: Text::with_section(action.title(), text_style.clone(), Default::default()),
+                                ..Default::default()
+                              });
+                            });
+                          // bound key
+                          parent
+                            .spawn_bundle(ButtonBundle {
+                              style: Style {
+                                size: Size::new(Val::Px(200.0), Val::Px(40.0)),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                ..Default::default()
+                              },
+                              material: materials.button_normal.clone(),
+                              ..Default::default()
+                            })
+                            .with_children(|parent| {
+                              parent.spawn_bundle(TextBundle {
+                                text: Text::with_section(binding, text_style.clone(), Default::default()),
+                                ..Default::default()
+                              });
+                            })
+                            .insert(st)
+                            .insert(item)
+                            .insert(SettingBindingButton)
+                            .insert(NavButtonUI);
+                        });
+                    }
                   });
               }
             });
 
-          // right elements
+          // reset key bindings button
           parent
-            .spawn_bundle(NodeBundle {
+            .spawn_bundle(ButtonBundle {
               style: Style {
-                size: Size::new(Val::Undefined, Val::Percent(100.0)),
-                flex_direction: FlexDirection::ColumnReverse,
-                align_items: AlignItems::FlexEnd,
+                size: Size::new(Val::Px(200.0), Val::Px(40.0)),
+                margin: Rect {
+                  top: Val::Px(20.0),
+                  ..Default::default()
+                },
+                align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..Default::default()
               },
-              material: materials.transparent.clone(),
+              material: materials.button_normal.clone(),
               ..Default::default()
             })
             .with_children(|parent| {
-              // right controllers
-              for (_, item) in setting_list.iter().cloned() {
-                let st = config.get_settings_type(&item);
+              parent.spawn_bundle(TextBundle {
+                text: Text::with_section("デフォルトに戻す", text_style.clone(), Default::default()),
+                ..Default::default()
+              });
+            })
+            .insert(ResetBindingsButton)
+            .insert(NavButtonUI);
+        });
+    });
+}
 
-                parent
-                  .spawn_bundle(NodeBundle {
-                    style: Style {
-                      size: Size::new(Val::Auto, Val::Px(50.0)),
-                      margin: Rect {
-                        left: Val::Px(20.0),
-                        right: Val::Px(20.0),
+/// spawn a page of settings, names on the left and controllers on the right
+fn spawn_settings_page(
+  parent: &mut ChildBuilder,
+  page: SettingsPage,
+  setting_list: Vec<(&str, SettingItem)>,
+  materials: &SettingsMaterials,
+  font_assets: &FontAssets,
+  config: &GameConfig,
+  monitors: &Monitors,
+) {
+  parent
+    .spawn_bundle(NodeBundle {
+      style: Style {
+        display: if page == SettingsPage::General {
+          Display::Flex
+        } else {
+          Display::None
+        },
+        size: Size::new(Val::Undefined, Val::Percent(100.0)),
+        margin: Rect::all(Val::Auto),
+        flex_direction: FlexDirection::Row,
+        justify_content: JustifyContent::SpaceBetween,
+        ..Default::default()
+      },
+      material: materials.transparent.clone(),
+      ..Default::default()
+    })
+    .insert(page)
+    .with_children(|parent| {
+      use SettingType::*;
+
+      // left name
+      parent
+        .spawn_bundle(NodeBundle {
+          style: Style {
+            size: Size::new(Val::Px(200.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::FlexEnd,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+          },
+          material: materials.transparent.clone(),
+          ..Default::default()
+        })
+        .with_children(|parent| {
+          for (name, _) in setting_list.iter() {
+            parent
+              .spawn_bundle(NodeBundle {
+                style: Style {
+                  size: Size::new(Val::Auto, Val::Px(50.0)),
+                  margin: Rect {
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                  },
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  ..Default::default()
+                },
+                material: materials.transparent.clone(),
+                ..Default::default()
+              })
+              .with_children(|parent| {
+                parent.spawn_bundle(TextBundle {
+                  text: Text::with_section(
+                    *name,
+                    TextStyle {
+                      font: font_assets.default_font.clone(),
+                      font_size: 32.0,
+                      color: Color::BLACK,
+                    },
+                    Default::default(),
+                  ),
+                  ..Default::default()
+                });
+              });
+          }
+        });
+
+      // right elements
+      parent
+        .spawn_bundle(NodeBundle {
+          style: Style {
+            size: Size::new(Val::Undefined, Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::FlexEnd,
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+          },
+          material: materials.transparent.clone(),
+          ..Default::default()
+        })
+        .with_children(|parent| {
+          // right controllers
+          for (_, item) in setting_list.iter().cloned() {
+            let st = config.get_settings_type(&item, monitors);
+
+            parent
+              .spawn_bundle(NodeBundle {
+                style: Style {
+                  size: Size::new(Val::Auto, Val::Px(50.0)),
+                  margin: Rect {
+                    left: Val::Px(20.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                  },
+                  align_items: AlignItems::Center,
+                  justify_content: JustifyContent::Center,
+                  ..Default::default()
+                },
+                material: materials.transparent.clone(),
+                ..Default::default()
+              })
+              .with_children(|parent| match &st {
+                String(value) => {
+                  // string values
+                  parent
+                    .spawn_bundle(ButtonBundle {
+                      material: materials.transparent.clone(),
+                      ..Default::default()
+                    })
+                    .with_children(|parent| {
+                      parent.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                          value,
+                          TextStyle {
+                            font: font_assets.default_font.clone(),
+                            font_size: 32.0,
+                            color: Color::BLACK,
+                          },
+                          Default::default(),
+                        ),
+                        ..Default::default()
+                      });
+                    })
+                    .insert(st)
+                    .insert(item)
+                    .insert(SettingStringButton);
+                }
+                Ratio(value) => {
+                  parent
+                    .spawn_bundle(ButtonBundle {
+                      style: Style {
+                        size: Size::new(Val::Px(25.0), Val::Px(25.0)),
                         ..Default::default()
                       },
-                      align_items: AlignItems::Center,
-                      justify_content: JustifyContent::Center,
+                      material: if *value {
+                        materials.radio_check_normal.clone()
+                      } else {
+                        materials.radio_uncheck_normal.clone()
+                      },
                       ..Default::default()
-                    },
-                    material: materials.transparent.clone(),
-                    ..Default::default()
-                  })
-                  .with_children(|parent| match &st {
-                    String(value) => {
-                      // string values
-                      parent
-                        .spawn_bundle(ButtonBundle {
-                          material: materials.transparent.clone(),
+                    })
+                    .insert(st)
+                    .insert(item)
+                    .insert(SettingRadioButton);
+                }
+                Slide(value) => {
+                  parent
+                    .spawn_bundle(ButtonBundle {
+                      style: Style {
+                        size: Size::new(Val::Auto, Val::Px(50.0)),
+                        padding: Rect {
+                          left: Val::Px(12.5),
+                          right: Val::Px(12.5),
                           ..Default::default()
-                        })
-                        .with_children(|parent| {
-                          parent.spawn_bundle(TextBundle {
-                            text: Text::with_section(
-                              value,
-                              TextStyle {
-                                font: font_assets.default_font.clone(),
-                                font_size: 32.0,
-                                color: Color::BLACK,
-                              },
-                              Default::default(),
-                            ),
-                            ..Default::default()
-                          });
-                        })
-                        .insert(st)
-                        .insert(item)
-                        .insert(SettingStringButton);
-                    }
-                    Ratio(value) => {
+                        },
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                      },
+                      material: materials.transparent.clone(),
+                      ..Default::default()
+                    })
+                    .with_children(|parent| {
                       parent
-                        .spawn_bundle(ButtonBundle {
+                        .spawn_bundle(NodeBundle {
                           style: Style {
-                            size: Size::new(Val::Px(25.0), Val::Px(25.0)),
+                            size: Size::new(Val::Px(SLIDER_LENGTH), Val::Px(10.0)),
                             ..Default::default()
                           },
-                          material: if *value {
-                            materials.radio_check_normal.clone()
-                          } else {
-                            materials.radio_uncheck_normal.clone()
-                          },
+                          material: materials.slide_bar.clone(),
                           ..Default::default()
                         })
-                        .insert(st)
-                        .insert(item)
-                        .insert(SettingRadioButton);
-                    }
-                    Slide(value) => {
+                        .insert(FocusPolicy::Pass);
                       parent
-                        .spawn_bundle(ButtonBundle {
+                        .spawn_bundle(NodeBundle {
                           style: Style {
-                            size: Size::new(Val::Auto, Val::Px(50.0)),
-                            padding: Rect {
-                              left: Val::Px(12.5),
-                              right: Val::Px(12.5),
+                            size: Size::new(Val::Px(25.0), Val::Px(25.0)),
+                            position_type: PositionType::Absolute,
+                            position: Rect {
+                              top: Val::Px(12.5),
+                              left: Val::Px(value * SLIDER_LENGTH),
                               ..Default::default()
                             },
-                            align_items: AlignItems::Center,
-                            justify_content: JustifyContent::Center,
                             ..Default::default()
                           },
-                          material: materials.transparent.clone(),
-                          ..Default::default()
-                        })
-                        .with_children(|parent| {
-                          parent
-                            .spawn_bundle(NodeBundle {
-                              style: Style {
-                                size: Size::new(Val::Px(SLIDER_LENGTH), Val::Px(10.0)),
-                                ..Default::default()
-                              },
-                              material: materials.slide_bar.clone(),
-                              ..Default::default()
-                            })
-                            .insert(FocusPolicy::Pass);
-                          parent
-                            .spawn_bundle(NodeBundle {
-                              style: Style {
-                                size: Size::new(Val::Px(25.0), Val::Px(25.0)),
-                                position_type: PositionType::Absolute,
-                                position: Rect {
-                                  top: Val::Px(12.5),
-                                  left: Val::Px(value * SLIDER_LENGTH),
-                                  ..Default::default()
-                                },
-                                ..Default::default()
-                              },
-                              material: materials.slide_button.clone(),
-                              ..Default::default()
-                            })
-                            .insert(FocusPolicy::Pass)
-                            .insert(SettingSlideMovingButton);
-                        })
-                        .insert(st)
-                        .insert(item)
-                        .insert(SettingSlideButton);
-                    }
-                    Select(selected, list) => {
-                      parent
-                        .spawn_bundle(ButtonBundle {
-                          material: materials.transparent.clone(),
+                          material: materials.slide_button.clone(),
                           ..Default::default()
                         })
-                        .with_children(|parent| {
-                          parent.spawn_bundle(TextBundle {
-                            text: Text::with_section(
-                              list[*selected as usize].clone(),
-                              TextStyle {
-                                font: font_assets.default_font.clone(),
-                                font_size: 32.0,
-                                color: Color::BLACK,
-                              },
-                              Default::default(),
-                            ),
-                            ..Default::default()
-                          });
-                        })
-                        .insert(st)
-                        .insert(item)
-                        .insert(SettingSelectButton);
-                    }
-                  });
-              }
-            });
+                        .insert(FocusPolicy::Pass)
+                        .insert(SettingSlideMovingButton);
+                    })
+                    .insert(st)
+                    .insert(item)
+                    .insert(SettingSlideButton);
+                }
+                Select(selected, list) => {
+                  parent
+                    .spawn_bundle(ButtonBundle {
+                      material: materials.transparent.clone(),
+                      ..Default::default()
+                    })
+                    .with_children(|parent| {
+                      parent.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                          list[*selected as usize].clone(),
+                          TextStyle {
+                            font: font_assets.default_font.clone(),
+                            font_size: 32.0,
+                            color: Color::BLACK,
+                          },
+                          Default::default(),
+                        ),
+                        ..Default::default()
+                      });
+                    })
+                    .insert(st)
+                    .insert(item)
+                    .insert(SettingSelectButton);
+                }
+                Binding(_) => {}
+              });
+          }
         });
     });
 }
@@ -488,26 +814,79 @@ fn nav_button_clicked(
     Query<(&SettingItem, &SettingType)>,
     Query<(&SettingItem, &mut SettingType)>,
   )>,
+  mut page_query: Query<(&SettingsPage, &mut Style)>,
+  mut windows: ResMut<Windows>,
+  mut window_size: ResMut<WindowSize>,
+  monitors: Res<Monitors>,
+  pending_display: Option<Res<PendingDisplayRevert>>,
+  capturing: Option<Res<CapturingBinding>>,
 ) {
+  // the click binding a mouse button belongs to the capture
+  if capturing.is_some() {
+    return;
+  }
   for (interaction, button) in query.iter() {
     match *interaction {
       Interaction::Clicked => match *button {
         NavButton::Back => {
-          state.pop().unwrap();
+          // display settings must be confirmed first
+          if pending_display.is_none() {
+            state.pop().unwrap();
+          }
         }
         NavButton::Apply => {
+          let old_save_dir = config.save_dir();
+          let old_display = config.display_settings();
           for (item, stype) in query_set.q0().iter() {
             config.apply_changes(item, stype);
           }
-          config
-            .save()
-            .expect("failed to save display config to disk");
+          // revert to the confirmed settings, even if applied twice
+          let previous = match &pending_display {
+            Some(pending) => pending.previous.clone(),
+            None => old_display.clone(),
+          };
+          let display_changed = config.display_settings() != old_display;
+          // unconfirmed display settings reach the disk only when kept
+          if display_changed || pending_display.is_some() {
+            config
+              .save_with_display(previous.clone())
+              .expect("failed to save config to disk");
+          } else {
+            config
+              .save()
+              .expect("failed to save config to disk");
+          }
           commands.insert_resource(config.get_window_descriptor());
+
+          if display_changed {
+            apply_display(&config, &monitors, &mut windows, &mut window_size);
+            commands.insert_resource(PendingDisplayRevert {
+              previous,
+              timer: Timer::from_seconds(DISPLAY_REVERT_SECONDS, false),
+            });
+          }
+
+          let new_save_dir = config.save_dir();
+          if new_save_dir != old_save_dir && !list_save_files(&old_save_dir).is_empty() {
+            commands.insert_resource(PendingSaveMove {
+              from: old_save_dir,
+              to: new_save_dir,
+            });
+          }
         }
         NavButton::Reset => {
           let config = GameConfig::default();
           for (item, mut stype) in query_set.q1_mut().iter_mut() {
-            *stype = config.get_settings_type(item);
+            *stype = config.get_settings_type(item, &monitors);
+          }
+        }
+        NavButton::Page(page) => {
+          for (settings_page, mut style) in page_query.iter_mut() {
+            style.display = if *settings_page == page {
+              Display::Flex
+            } else {
+              Display::None
+            };
           }
         }
       },
@@ -525,8 +904,12 @@ enum SettingsInputTextReason {
 fn string_button_clicked(
   mut commands: Commands,
   mut state: ResMut<State<AppState>>,
-  query: Query<(&Interaction, Entity, &SettingType), With<SettingStringButton>>,
+  capturing: Option<Res<CapturingBinding>>,
+  query: Query<(&Interaction, Entity, &SettingType), (Changed<Interaction>, With<SettingStringButton>)>,
 ) {
+  if capturing.is_some() {
+    return;
+  }
   for (interaction, entity, stype) in query.iter() {
     match *interaction {
       Interaction::Clicked => {
@@ -543,11 +926,15 @@ fn string_button_clicked(
 
 /// listen click events to radio typed buttons
 fn radio_button_clicked(
+  capturing: Option<Res<CapturingBinding>>,
   mut query: Query<
     (&Interaction, &mut SettingType),
     (Changed<Interaction>, With<SettingRadioButton>),
   >,
 ) {
+  if capturing.is_some() {
+    return;
+  }
   for (interaction, mut stype) in query.iter_mut() {
     match *interaction {
       Interaction::Clicked => {
@@ -602,11 +989,15 @@ fn update_radio_material(
 
 /// listen click events to select typed buttons
 fn select_button_clicked(
+  capturing: Option<Res<CapturingBinding>>,
   mut query: Query<
     (&Interaction, &mut SettingType),
     (Changed<Interaction>, With<SettingSelectButton>),
   >,
 ) {
+  if capturing.is_some() {
+    return;
+  }
   for (interaction, mut stype) in query.iter_mut() {
     if let SettingType::Select(choice, list) = &*stype {
       match *interaction {
@@ -619,6 +1010,47 @@ fn select_button_clicked(
   }
 }
 
+/// the resolution list follows the selected monitor
+fn update_resolution_list(
+  monitors: Res<Monitors>,
+  mut query: Query<(&SettingItem, &mut SettingType), With<SettingSelectButton>>,
+) {
+  let mut monitor = None;
+  for (item, stype) in query.iter_mut() {
+    if let (SettingItem::Monitor, SettingType::Select(index, _)) = (item, &*stype) {
+      // a freshly spawned button already comes with the list of the configured monitor
+      if stype.is_changed() && !stype.is_added() {
+        monitor = Some(*index);
+      }
+    }
+  }
+  let monitor = match monitor {
+    Some(monitor) => monitor,
+    None => return,
+  };
+
+  let resolutions: Vec<String> = monitors
+    .resolutions(monitor)
+    .iter()
+    .map(|(w, h)| format!("{}x{}", w, h))
+    .collect();
+  for (item, mut stype) in query.iter_mut() {
+    if let (SettingItem::Resolution, SettingType::Select(index, old_list)) = (item, &*stype) {
+      // keep the selected resolution, even if the new monitor does not list it
+      let current = &old_list[*index];
+      let mut list = resolutions.clone();
+      if !list.contains(current) {
+        list.push(current.clone());
+      }
+      if *old_list == list {
+        continue;
+      }
+      let selected = list.iter().position(|r| r == current).unwrap();
+      *stype = SettingType::Select(selected, list);
+    }
+  }
+}
+
 /// update selected options of select typed button after clicked
 fn update_select_button(
   query: Query<(&SettingType, &Children), (Changed<SettingType>, With<SettingSelectButton>)>,
@@ -640,14 +1072,24 @@ fn resume_settings(
   mut commands: Commands,
   reason: Option<Res<SettingsInputTextReason>>,
   input: Option<Res<TextInputText>>,
-  mut query: Query<&mut SettingType>,
+  mut query: Query<(&SettingItem, &mut SettingType)>,
+  mut message_query: Query<&mut Text, With<SettingsMessageUI>>,
 ) {
   if let Some(reason) = reason {
     match *reason {
       SettingsInputTextReason::ChangeStringValue(entity) => {
-        if let Ok(mut stype) = query.get_mut(entity) {
+        if let Ok((item, mut stype)) = query.get_mut(entity) {
           if let Some(input) = input {
-            *stype = SettingType::String(input.0.clone());
+            let message = match item {
+              SettingItem::SaveDir => check_save_dir(&PathBuf::from(&input.0)).err(),
+              _ => None,
+            };
+            if message.is_none() {
+              *stype = SettingType::String(input.0.clone());
+            }
+            for mut text in message_query.iter_mut() {
+              text.sections[0].value = message.clone().unwrap_or_default();
+            }
             commands.remove_resource::<TextInputText>();
           }
         }
@@ -656,6 +1098,292 @@ fn resume_settings(
   }
 }
 
+/// ask whether to move existing saves after the save directory changed
+fn setup_move_saves_dialog(
+  mut commands: Commands,
+  materials: Res<SettingsMaterials>,
+  font_assets: Res<FontAssets>,
+  pending: Option<Res<PendingSaveMove>>,
+  root_query: Query<Entity, With<SettingsUI>>,
+) {
+  let pending = match pending {
+    Some(pending) if pending.is_added() => pending,
+    _ => return,
+  };
+  let text_style = TextStyle {
+    font: font_assets.default_font.clone(),
+    font_size: 24.0,
+    color: Color::BLACK,
+  };
+
+  for root in root_query.iter() {
+    commands.entity(root).with_children(|parent| {
+      parent
+        .spawn_bundle(NodeBundle {
+          style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+              left: Val::Percent(25.0),
+              top: Val::Percent(35.0),
+              ..Default::default()
+            },
+            size: Size::new(Val::Percent(50.0), Val::Percent(30.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::SpaceAround,
+            ..Default::default()
+          },
+          material: materials.dialog_background.clone(),
+          ..Default::default()
+        })
+        .insert(MoveSavesDialogUI)
+        .with_children(|parent| {
+          parent.spawn_bundle(TextBundle {
+            text: Text::with_section(
+              format!(
+                "Move saves from {} to {}?",
+                pending.from.display(),
+                pending.to.display()
+              ),
+              text_style.clone(),
+              Default::default(),
+            ),
+            ..Default::default()
+          });
+          parent
+            .spawn_bundle(NodeBundle {
+              material: materials.transparent.clone(),
+              ..Default::default()
+            })
+            .with_children(|parent| {
+              for (button, title) in vec![(MoveSavesButton::Move, "移動"), (MoveSavesButton::Keep, "そのまま")] {
+                parent
+                  .spawn_bundle(ButtonBundle {
+                    style: Style {
+                      size: Size::new(Val::Px(200.0), Val::Px(40.0)),
+                      margin: Rect::all(Val::Px(10.0)),
+                      align_items: AlignItems::Center,
+                      justify_content: JustifyContent::Center,
+                      ..Default::default()
+                    },
+                    material: materials.button_normal.clone(),
+                    ..Default::default()
+                  })
+                  .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                      text: Text::with_section(title, text_style.clone(), Default::default()),
+                      ..Default::default()
+                    });
+                  })
+                  .insert(button)
+                  .insert(NavButtonUI);
+              }
+            });
+        });
+    });
+  }
+}
+
+/// listen click events to the "move saves" dialog
+fn move_saves_dialog_clicked(
+  mut commands: Commands,
+  pending: Option<Res<PendingSaveMove>>,
+  query: Query<(&Interaction, &MoveSavesButton), Changed<Interaction>>,
+  dialog_query: Query<Entity, With<MoveSavesDialogUI>>,
+  mut message_query: Query<&mut Text, With<SettingsMessageUI>>,
+) {
+  let pending = match pending {
+    Some(pending) => pending,
+    None => return,
+  };
+  for (interaction, button) in query.iter() {
+    if *interaction != Interaction::Clicked {
+      continue;
+    }
+    let message = match *button {
+      MoveSavesButton::Move => match move_saves(&pending.from, &pending.to) {
+        Ok(moved) => format!("{} save files moved", moved),
+        Err(err) => format!("failed to move saves: {}", err),
+      },
+      MoveSavesButton::Keep => String::new(),
+    };
+    for mut text in message_query.iter_mut() {
+      text.sections[0].value = message.clone();
+    }
+    for entity in dialog_query.iter() {
+      commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<PendingSaveMove>();
+  }
+}
+
+/// push display settings to the window.
+/// `WindowSize` is updated directly, resize events are not sent in every case.
+fn apply_display(
+  config: &GameConfig,
+  monitors: &Monitors,
+  windows: &mut Windows,
+  window_size: &mut WindowSize,
+) {
+  if let Some(window) = windows.get_primary_mut() {
+    config.apply_to_window(window, monitors);
+  }
+  let descriptor = config.get_window_descriptor();
+  window_size.width = descriptor.width;
+  window_size.height = descriptor.height;
+}
+
+/// ask whether to keep new display settings
+fn setup_keep_display_dialog(
+  mut commands: Commands,
+  materials: Res<SettingsMaterials>,
+  font_assets: Res<FontAssets>,
+  pending: Option<Res<PendingDisplayRevert>>,
+  root_query: Query<Entity, With<SettingsUI>>,
+  dialog_query: Query<Entity, With<KeepDisplayDialogUI>>,
+) {
+  match pending {
+    Some(pending) if pending.is_added() => {}
+    _ => return,
+  }
+  // applied again while the dialog is open
+  for entity in dialog_query.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+  let text_style = TextStyle {
+    font: font_assets.default_font.clone(),
+    font_size: 24.0,
+    color: Color::BLACK,
+  };
+
+  for root in root_query.iter() {
+    commands.entity(root).with_children(|parent| {
+      parent
+        .spawn_bundle(NodeBundle {
+          style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+              left: Val::Percent(25.0),
+              top: Val::Percent(35.0),
+              ..Default::default()
+            },
+            size: Size::new(Val::Percent(50.0), Val::Percent(30.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::SpaceAround,
+            ..Default::default()
+          },
+          material: materials.dialog_background.clone(),
+          ..Default::default()
+        })
+        .insert(KeepDisplayDialogUI)
+        .with_children(|parent| {
+          parent.spawn_bundle(TextBundle {
+            text: Text::with_section("この設定を保持しますか？", text_style.clone(), Default::default()),
+            ..Default::default()
+          });
+          parent
+            .spawn_bundle(TextBundle {
+              text: Text::with_section("", text_style.clone(), Default::default()),
+              ..Default::default()
+            })
+            .insert(KeepDisplayCountdownUI);
+          parent
+            .spawn_bundle(NodeBundle {
+              material: materials.transparent.clone(),
+              ..Default::default()
+            })
+            .with_children(|parent| {
+              for (button, title) in vec![(KeepDisplayButton::Keep, "保持"), (KeepDisplayButton::Revert, "元に戻す")] {
+                parent
+                  .spawn_bundle(ButtonBundle {
+                    style: Style {
+                      size: Size::new(Val::Px(200.0), Val::Px(40.0)),
+                      margin: Rect::all(Val::Px(10.0)),
+                      align_items: AlignItems::Center,
+                      justify_content: JustifyContent::Center,
+                      ..Default::default()
+                    },
+                    material: materials.button_normal.clone(),
+                    ..Default::default()
+                  })
+                  .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                      text: Text::with_section(title, text_style.clone(), Default::default()),
+                      ..Default::default()
+                    });
+                  })
+                  .insert(button)
+                  .insert(NavButtonUI);
+              }
+            });
+        });
+    });
+  }
+}
+
+/// count down the "keep display settings" dialog, revert if not kept in time
+fn keep_display_dialog(
+  mut commands: Commands,
+  time: Res<Time>,
+  mut config: ResMut<GameConfig>,
+  mut windows: ResMut<Windows>,
+  mut window_size: ResMut<WindowSize>,
+  monitors: Res<Monitors>,
+  pending: Option<ResMut<PendingDisplayRevert>>,
+  query: Query<(&Interaction, &KeepDisplayButton), Changed<Interaction>>,
+  dialog_query: Query<Entity, With<KeepDisplayDialogUI>>,
+  mut countdown_query: Query<&mut Text, With<KeepDisplayCountdownUI>>,
+  mut item_query: Query<(&SettingItem, &mut SettingType)>,
+) {
+  let mut pending = match pending {
+    Some(pending) => pending,
+    None => return,
+  };
+  let finished = pending.timer.tick(time.delta()).finished();
+
+  let mut keep = None;
+  for (interaction, button) in query.iter() {
+    if *interaction == Interaction::Clicked {
+      keep = Some(matches!(button, KeepDisplayButton::Keep));
+    }
+  }
+  let keep = match keep {
+    Some(keep) => keep,
+    None if finished => false,
+    None => {
+      let remaining = pending.timer.duration() - pending.timer.elapsed();
+      let value = format!("{} 秒後に元に戻ります", remaining.as_secs_f32().ceil());
+      for mut text in countdown_query.iter_mut() {
+        if text.sections[0].value != value {
+          text.sections[0].value = value.clone();
+        }
+      }
+      return;
+    }
+  };
+
+  if keep {
+    // the new display settings are confirmed, persist them
+    config
+      .save()
+      .expect("failed to save config to disk");
+  } else {
+    config.set_display_settings(pending.previous.clone());
+    commands.insert_resource(config.get_window_descriptor());
+    apply_display(&config, &monitors, &mut windows, &mut window_size);
+    for (item, mut stype) in item_query.iter_mut() {
+      if item.is_display() {
+        *stype = config.get_settings_type(item, &monitors);
+      }
+    }
+  }
+  for entity in dialog_query.iter() {
+    commands.entity(entity).despawn_recursive();
+  }
+  commands.remove_resource::<PendingDisplayRevert>();
+}
+
 /// update showing string of string typed settings after text changed
 fn update_string_settings(
   query: Query<(&SettingType, &Children), (Changed<SettingType>, With<SettingStringButton>)>,
@@ -675,10 +1403,12 @@ fn update_string_settings(
 /// update materials of slider typed button when hovered/clicked
 fn slide_button_clicked(
   mut commands: Commands,
+  capturing: Option<Res<CapturingBinding>>,
   query: Query<(&Interaction, Entity), (Changed<Interaction>, With<SettingSlideButton>)>,
 ) {
   for (interaction, entity) in query.iter() {
     match *interaction {
+      Interaction::Clicked if capturing.is_some() => {}
       Interaction::Clicked => {
         commands.insert_resource(SettingDraggingSlide(entity));
       }
@@ -705,6 +1435,28 @@ fn drag_slide_button(
   }
 }
 
+/// preview volume sliders before they are applied
+fn preview_volumes(
+  mut mixer: ResMut<AudioMixer>,
+  query: Query<(&SettingItem, &SettingType), Changed<SettingType>>,
+) {
+  let mut volumes = mixer.volumes();
+  for (item, stype) in query.iter() {
+    if let SettingType::Slide(value) = *stype {
+      match item {
+        SettingItem::Volume => volumes.master = value,
+        SettingItem::VolumeMusic => volumes.music = value,
+        SettingItem::VolumeSfx => volumes.sfx = value,
+        SettingItem::VolumeVoice => volumes.voice = value,
+        _ => {}
+      }
+    }
+  }
+  if volumes != mixer.volumes() {
+    mixer.set_volumes(volumes);
+  }
+}
+
 /// update the position of the slide dragging button
 fn update_slide_button(
   query: Query<(&SettingType, &Children), With<SettingSlideButton>>,
@@ -721,11 +1473,164 @@ fn update_slide_button(
   }
 }
 
+/// listen click events to key binding buttons and start capturing
+fn binding_button_clicked(
+  mut commands: Commands,
+  capturing: Option<Res<CapturingBinding>>,
+  query: Query<(&Interaction, Entity), (Changed<Interaction>, With<SettingBindingButton>)>,
+) {
+  // the click finishing a capture should not start another one
+  if capturing.is_some() {
+    return;
+  }
+  for (interaction, entity) in query.iter() {
+    if *interaction == Interaction::Clicked {
+      commands.insert_resource(CapturingBinding(entity));
+    }
+  }
+}
+
+/// bind the next pressed key or mouse button, escape to cancel
+fn capture_binding(
+  mut commands: Commands,
+  capturing: Option<Res<CapturingBinding>>,
+  keyboard_input: Res<Input<KeyCode>>,
+  mouse_input: Res<Input<MouseButton>>,
+  mut query: Query<&mut SettingType, With<SettingBindingButton>>,
+) {
+  let capturing = match capturing {
+    Some(capturing) => capturing,
+    None => return,
+  };
+  if keyboard_input.just_pressed(KeyCode::Escape) {
+    commands.remove_resource::<CapturingBinding>();
+    return;
+  }
+
+  let binding = keyboard_input
+    .get_just_pressed()
+    .next()
+    .map(|key| InputBinding::Key(*key))
+    .or_else(|| mouse_input.get_just_pressed().next().map(|button| InputBinding::Mouse(*button)));
+  if let Some(binding) = binding {
+    if let Ok(mut stype) = query.get_mut(capturing.0) {
+      *stype = SettingType::Binding(binding);
+    }
+    commands.remove_resource::<CapturingBinding>();
+  }
+}
+
+/// update showing keys of binding buttons, actions sharing a key are marked red
+fn update_binding_buttons(
+  capturing: Option<Res<CapturingBinding>>,
+  query: Query<(Entity, &SettingItem, &SettingType, &Children), With<SettingBindingButton>>,
+  changed_query: Query<(), (Changed<SettingType>, With<SettingBindingButton>)>,
+  mut text_query: Query<&mut Text, Without<SettingsMessageUI>>,
+  mut message_query: Query<&mut Text, With<SettingsMessageUI>>,
+) {
+  let bindings: Vec<(InputAction, InputBinding)> = query
+    .iter()
+    .filter_map(|(_, item, stype, _)| match (item, stype) {
+      (SettingItem::KeyBinding(action), SettingType::Binding(binding)) => Some((*action, *binding)),
+      _ => None,
+    })
+    .collect();
+
+  for (entity, item, stype, children) in query.iter() {
+    if let (SettingItem::KeyBinding(action), SettingType::Binding(binding)) = (item, stype) {
+      let value = match &capturing {
+        Some(capturing) if capturing.0 == entity => "...".to_string(),
+        _ => binding.title(),
+      };
+      let conflicted = bindings.iter().any(|(a, b)| a != action && b == binding);
+      let color = if conflicted {
+        Color::rgb(0.8, 0.2, 0.2)
+      } else {
+        Color::BLACK
+      };
+      for child in children.iter() {
+        if let Ok(mut text) = text_query.get_mut(*child) {
+          if text.sections[0].value != value || text.sections[0].style.color != color {
+            text.sections[0].value = value.clone();
+            text.sections[0].style.color = color;
+          }
+        }
+      }
+    }
+  }
+
+  // list conflicts only after bindings changed, the message is shared with other settings
+  if changed_query.iter().next().is_none() {
+    return;
+  }
+  let mut conflicts = Vec::new();
+  for (index, (_, binding)) in bindings.iter().enumerate() {
+    if bindings[..index].iter().any(|(_, b)| b == binding) {
+      continue;
+    }
+    let actions: Vec<&str> = bindings
+      .iter()
+      .filter(|(_, b)| b == binding)
+      .map(|(action, _)| action.title())
+      .collect();
+    if actions.len() > 1 {
+      conflicts.push(format!("{}: {}", binding.title(), actions.join(" / ")));
+    }
+  }
+  for mut text in message_query.iter_mut() {
+    text.sections[0].value = if conflicts.is_empty() {
+      String::new()
+    } else {
+      format!("キーが重複しています\n{}", conflicts.join("\n"))
+    };
+  }
+}
+
+/// reset all key bindings to default, still need to be applied
+fn reset_bindings_clicked(
+  monitors: Res<Monitors>,
+  capturing: Option<Res<CapturingBinding>>,
+  query: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+  mut binding_query: Query<(&SettingItem, &mut SettingType), With<SettingBindingButton>>,
+) {
+  if capturing.is_some() {
+    return;
+  }
+  for interaction in query.iter() {
+    if *interaction == Interaction::Clicked {
+      let config = GameConfig::default();
+      for (item, mut stype) in binding_query.iter_mut() {
+        *stype = config.get_settings_type(item, &monitors);
+      }
+    }
+  }
+}
+
 /// destroy settings page
-fn destroy_settings(mut commands: Commands, query: Query<Entity, With<SettingsUI>>) {
+fn destroy_settings(
+  mut commands: Commands,
+  mut config: ResMut<GameConfig>,
+  mut mixer: ResMut<AudioMixer>,
+  mut windows: ResMut<Windows>,
+  mut window_size: ResMut<WindowSize>,
+  monitors: Res<Monitors>,
+  pending_display: Option<Res<PendingDisplayRevert>>,
+  query: Query<Entity, With<SettingsUI>>,
+) {
   for entity in query.iter() {
     commands.entity(entity).despawn_recursive();
   }
+  // drop previewed volumes which are not applied
+  mixer.set_volumes(config.audio_volumes());
+  // leaving before keeping the display settings reverts them
+  if let Some(pending) = pending_display {
+    config.set_display_settings(pending.previous.clone());
+    commands.insert_resource(config.get_window_descriptor());
+    apply_display(&config, &monitors, &mut windows, &mut window_size);
+  }
+  commands.remove_resource::<PendingSaveMove>();
+  commands.remove_resource::<PendingDisplayRevert>();
+  commands.remove_resource::<CapturingBinding>();
 }
 
 fn hide_ui(mut query: Query<&mut Style, With<SettingsUI>>) {
@@ -747,7 +1652,11 @@ impl Plugin for SettingsPlugin {
   fn build(&self, app: &mut App) {
     app
       .init_resource::<SettingsMaterials>()
-      .add_system_set(SystemSet::on_enter(AppState::Settings).with_system(setup_settings))
+      .add_system_set(
+        SystemSet::on_enter(AppState::Settings)
+          .with_system(refresh_monitors.label("monitors"))
+          .with_system(setup_settings.after("monitors")),
+      )
       .add_system_set(
         SystemSet::on_update(AppState::Settings)
           .with_system(nav_button_material_change)
@@ -756,11 +1665,21 @@ impl Plugin for SettingsPlugin {
           .with_system(radio_button_clicked.label("clicked"))
           .with_system(select_button_clicked.label("clicked"))
           .with_system(slide_button_clicked.label("clicked"))
+          .with_system(binding_button_clicked.label("clicked"))
+          .with_system(reset_bindings_clicked.label("clicked"))
+          .with_system(capture_binding.label("clicked"))
           .with_system(update_string_settings.after("clicked"))
           .with_system(update_radio_material.after("clicked"))
+          .with_system(update_resolution_list.after("clicked"))
           .with_system(update_select_button.after("clicked"))
           .with_system(drag_slide_button.after("clicked"))
-          .with_system(update_slide_button.after("clicked")),
+          .with_system(update_slide_button.after("clicked"))
+          .with_system(preview_volumes.after("clicked"))
+          .with_system(update_binding_buttons.after("clicked"))
+          .with_system(setup_move_saves_dialog)
+          .with_system(move_saves_dialog_clicked)
+          .with_system(setup_keep_display_dialog)
+          .with_system(keep_display_dialog),
       )
       .add_system_set(
         SystemSet::on_pause(AppState::Settings)
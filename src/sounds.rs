@@ -1,7 +1,34 @@
+use crate::assets::asset_path;
+use crate::config::GameConfig;
+use crate::consts::*;
+use bevy::asset::HandleId;
+use bevy::asset::LoadState;
+use bevy::audio::Decodable;
+use bevy::ecs::system::NonSendMut;
 use bevy::prelude::*;
+use rodio::source::ChannelVolume;
+use rodio::{OutputStream, OutputStreamHandle, Sink};
 
 pub struct SoundEffects {
   pub tape: Handle<AudioSource>,
+
+  // combat sounds stay silent until they are recorded
+  pub swing: Option<Handle<AudioSource>>,
+  pub hit: Option<Handle<AudioSource>>,
+  pub shoot: Option<Handle<AudioSource>>,
+  pub counter_attack: Option<Handle<AudioSource>>,
+  pub defeat: Option<Handle<AudioSource>>,
+  pub soul_gain: Option<Handle<AudioSource>>,
+}
+
+/// load a sound only if its file is in `assets`
+fn load_recorded(assets: &AssetServer, path: &str) -> Option<Handle<AudioSource>> {
+  if asset_path(path).is_file() {
+    Some(assets.load(path))
+  } else {
+    info!("{} is not recorded yet, the sound stays silent", path);
+    None
+  }
 }
 
 impl FromWorld for SoundEffects {
@@ -10,14 +37,240 @@ impl FromWorld for SoundEffects {
 
     SoundEffects {
       tape: assets.load("sounds/tape.mp3"),
+
+      swing: load_recorded(assets, "sounds/swing.mp3"),
+      hit: load_recorded(assets, "sounds/hit.mp3"),
+      shoot: load_recorded(assets, "sounds/shoot.mp3"),
+      counter_attack: load_recorded(assets, "sounds/counter_attack.mp3"),
+      defeat: load_recorded(assets, "sounds/defeat.mp3"),
+      soul_gain: load_recorded(assets, "sounds/soul_gain.mp3"),
     }
   }
 }
 
+/// Category of a sound, each of them has its own volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBus {
+  Music,
+  Sfx,
+  Voice,
+}
+
+/// Volumes of the master and every bus, ranged from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioVolumes {
+  pub master: f32,
+  pub music: f32,
+  pub sfx: f32,
+  pub voice: f32,
+}
+
+impl AudioVolumes {
+  /// effective volume of sounds in the bus
+  pub fn get(&self, bus: AudioBus) -> f32 {
+    self.master
+      * match bus {
+        AudioBus::Music => self.music,
+        AudioBus::Sfx => self.sfx,
+        AudioBus::Voice => self.voice,
+      }
+  }
+}
+
+/// How a sound is played
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoundOptions {
+  /// stereo position from -1.0 (left) to 1.0 (right), `None` keeps the channels
+  pub pan: Option<f32>,
+  /// most instances of the same sound playing at once, new ones are dropped
+  pub limit: Option<usize>,
+}
+
+struct QueuedSound {
+  bus: AudioBus,
+  source: Handle<AudioSource>,
+  options: SoundOptions,
+  /// seconds waited for the source to load
+  waited: f32,
+}
+
+/// Plays sounds through buses, use it instead of `Audio`
+pub struct AudioMixer {
+  volumes: AudioVolumes,
+  queue: Vec<QueuedSound>,
+}
+
+impl FromWorld for AudioMixer {
+  fn from_world(world: &mut World) -> Self {
+    let config = world.get_resource::<GameConfig>().unwrap();
+
+    AudioMixer {
+      volumes: config.audio_volumes(),
+      queue: Vec::new(),
+    }
+  }
+}
+
+impl AudioMixer {
+  pub fn play(&mut self, bus: AudioBus, source: Handle<AudioSource>) {
+    self.play_with(bus, source, SoundOptions::default());
+  }
+
+  pub fn play_with(&mut self, bus: AudioBus, source: Handle<AudioSource>, options: SoundOptions) {
+    self.queue.push(QueuedSound {
+      bus,
+      source,
+      options,
+      waited: 0.0,
+    });
+  }
+
+  pub fn volumes(&self) -> AudioVolumes {
+    self.volumes
+  }
+
+  /// change volumes, sounds already playing follow them
+  pub fn set_volumes(&mut self, volumes: AudioVolumes) {
+    self.volumes = volumes;
+  }
+}
+
+/// The output device and sounds playing on it
+pub struct AudioOutput {
+  // the stream stops the sound when dropped
+  _stream: Option<OutputStream>,
+  handle: Option<OutputStreamHandle>,
+  sinks: Vec<PlayingSound>,
+}
+
+struct PlayingSound {
+  bus: AudioBus,
+  source: HandleId,
+  sink: Sink,
+}
+
+impl Default for AudioOutput {
+  fn default() -> Self {
+    match OutputStream::try_default() {
+      Ok((stream, handle)) => AudioOutput {
+        _stream: Some(stream),
+        handle: Some(handle),
+        sinks: Vec::new(),
+      },
+      Err(err) => {
+        warn!("no audio output device available: {}", err);
+        AudioOutput {
+          _stream: None,
+          handle: None,
+          sinks: Vec::new(),
+        }
+      }
+    }
+  }
+}
+
+impl AudioOutput {
+  /// start a sound which is controlled by the caller
+  pub fn play(&self, source: &AudioSource, volume: f32) -> Option<Sink> {
+    let sink = Sink::try_new(self.handle.as_ref()?).ok()?;
+    sink.set_volume(volume);
+    sink.append(source.decoder());
+    Some(sink)
+  }
+
+  /// start a sound mixed to mono and placed between the speakers
+  pub fn play_panned(&self, source: &AudioSource, volume: f32, pan: f32) -> Option<Sink> {
+    let sink = Sink::try_new(self.handle.as_ref()?).ok()?;
+    // constant power panning
+    let angle = (pan.max(-1.0).min(1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    sink.set_volume(volume);
+    sink.append(ChannelVolume::new(source.decoder(), vec![angle.cos(), angle.sin()]));
+    Some(sink)
+  }
+
+  fn count_playing(&self, source: HandleId) -> usize {
+    self.sinks.iter().filter(|sound| sound.source == source).count()
+  }
+}
+
+/// play queued sounds once they are loaded
+fn play_queued_sounds(
+  time: Res<Time>,
+  mut mixer: ResMut<AudioMixer>,
+  mut output: NonSendMut<AudioOutput>,
+  asset_server: Res<AssetServer>,
+  sources: Res<Assets<AudioSource>>,
+) {
+  // do not touch the mixer unless needed, its changes update the volumes
+  if mixer.queue.is_empty() {
+    return;
+  }
+  let volumes = mixer.volumes;
+  let mut queue = Vec::new();
+  for mut sound in mixer.queue.drain(..) {
+    let source = match sources.get(&sound.source) {
+      Some(source) => source,
+      None => {
+        if asset_server.get_load_state(&sound.source) == LoadState::Failed {
+          warn!("dropped a sound which failed to load");
+          continue;
+        }
+        sound.waited += time.delta_seconds();
+        if sound.waited < SOUND_MAX_QUEUED_SECONDS {
+          queue.push(sound);
+        }
+        continue;
+      }
+    };
+    let id = sound.source.id;
+    if let Some(limit) = sound.options.limit {
+      if output.count_playing(id) >= limit {
+        continue;
+      }
+    }
+    let volume = volumes.get(sound.bus);
+    let sink = match sound.options.pan {
+      Some(pan) => output.play_panned(source, volume, pan),
+      None => output.play(source, volume),
+    };
+    if let Some(sink) = sink {
+      output.sinks.push(PlayingSound {
+        bus: sound.bus,
+        source: id,
+        sink,
+      });
+    }
+  }
+  mixer.queue = queue;
+}
+
+/// apply volume changes to playing sounds and drop finished ones
+fn update_playing_sounds(mixer: Res<AudioMixer>, mut output: NonSendMut<AudioOutput>) {
+  output.sinks.retain(|sound| !sound.sink.empty());
+  if mixer.is_changed() {
+    for sound in output.sinks.iter() {
+      sound.sink.set_volume(mixer.volumes.get(sound.bus));
+    }
+  }
+}
+
+/// follow volumes in the config after it is applied
+fn sync_config_volumes(config: Res<GameConfig>, mut mixer: ResMut<AudioMixer>) {
+  if config.is_changed() && mixer.volumes != config.audio_volumes() {
+    mixer.set_volumes(config.audio_volumes());
+  }
+}
+
 pub struct SoundEffectsPlugin;
 
 impl Plugin for SoundEffectsPlugin {
   fn build(&self, app: &mut App) {
-    app.init_resource::<SoundEffects>();
+    app
+      .init_resource::<SoundEffects>()
+      .init_resource::<AudioMixer>()
+      .init_non_send_resource::<AudioOutput>()
+      .add_system_to_stage(CoreStage::PostUpdate, sync_config_volumes.label("volumes"))
+      .add_system_to_stage(CoreStage::PostUpdate, play_queued_sounds.after("volumes"))
+      .add_system_to_stage(CoreStage::PostUpdate, update_playing_sounds.after("volumes"));
   }
 }